@@ -0,0 +1,149 @@
+use clap::{Parser, Subcommand};
+use shared::{AdminLogin, JoinRoom, Message, SubscribeResult};
+use std::net::TcpStream;
+use std::process;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(
+        short = 'a',
+        long,
+        required = false,
+        default_value = "localhost:7878",
+        value_parser
+    )]
+    addr: String,
+    /// Game room to control, instead of the server default one
+    #[clap(short = 'r', long, value_parser)]
+    room: Option<String>,
+    /// Admin token of the server (its `--admin-token`), needed by every command but `watch`
+    #[clap(short = 't', long, value_parser)]
+    token: Option<String>,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Seal registrations and start the game, exiting with 1 if the server refuses
+    Start,
+    /// List the registered players
+    Players,
+    /// End the game right away
+    Abort,
+    /// Follow the game as a spectator and print what is broadcast until `EndOfGame`
+    Watch,
+}
+
+fn main() {
+    let args = Args::parse();
+    let stream = match TcpStream::connect(&args.addr) {
+        Ok(stream) => stream,
+        Err(err) => {
+            eprintln!("Cannot connect to {}: {}", args.addr, err);
+            process::exit(1);
+        }
+    };
+    if let Some(room) = args.room {
+        if let Err(err) = shared::write_message(&stream, Message::JoinRoom(JoinRoom { room })) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+    if !matches!(args.command, Command::Watch) {
+        let token = match args.token {
+            Some(token) => token,
+            None => {
+                eprintln!("This command needs the admin token of the server (--token)");
+                process::exit(1);
+            }
+        };
+        if let Err(err) = login(&stream, token) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+    let result = match args.command {
+        Command::Start => start(&stream),
+        Command::Abort => shared::write_message(&stream, Message::AbortGame),
+        Command::Players => list_players(&stream),
+        Command::Watch => watch(&stream),
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+/// Opens the controller session; the server closes the connection on a wrong token
+fn login(stream: &TcpStream, token: String) -> std::io::Result<()> {
+    shared::write_message(stream, Message::AdminLogin(AdminLogin { token }))?;
+    match shared::receive_message(stream) {
+        Ok(Message::SubscribeResult(SubscribeResult::Ok)) => Ok(()),
+        Ok(message) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unexpected message from the server: {:?}", message),
+        )),
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "The server refused the admin token",
+        )),
+    }
+}
+
+/// The server answers `SubscribeResult::Ok`, or the registered players when the game is
+/// already running or has too few of them
+fn start(stream: &TcpStream) -> std::io::Result<()> {
+    shared::write_message(stream, Message::StartGame)?;
+    match shared::receive_message(stream)? {
+        Message::SubscribeResult(SubscribeResult::Ok) => Ok(()),
+        Message::PublicLeaderBoard(leader_board) => {
+            print!("{}", leader_board);
+            Err(std::io::Error::other(
+                "The server did not start the game: it is already running or has too few players",
+            ))
+        }
+        message => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unexpected message from the server: {:?}", message),
+        )),
+    }
+}
+
+fn list_players(stream: &TcpStream) -> std::io::Result<()> {
+    shared::write_message(stream, Message::ListPlayers)?;
+    match shared::receive_message(stream)? {
+        Message::PublicLeaderBoard(leader_board) => {
+            print!("{}", leader_board);
+            Ok(())
+        }
+        message => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unexpected message from the server: {:?}", message),
+        )),
+    }
+}
+
+fn watch(stream: &TcpStream) -> std::io::Result<()> {
+    shared::write_message(stream, Message::Hello)?;
+    shared::write_message(stream, Message::Spectate)?;
+    loop {
+        match shared::receive_message(stream)? {
+            Message::PublicLeaderBoard(leader_board) => print!("{}", leader_board),
+            Message::RoundSummary(round_summary) => {
+                for result in round_summary.chain {
+                    println!(
+                        "{}: {} {:?}",
+                        round_summary.challenge, result.name, result.value
+                    );
+                }
+            }
+            Message::EndOfGame(end_of_game) => {
+                print!("Game over\n{}", end_of_game.leader_board);
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use shared::challenges::monstrous_maze::{
+    END_CHARACTER, FREE_WAY_CHARACTER, MONSTER_CHARACTER, START_CHARACTER,
+};
+use shared::{Challenge, MD5HashCashInput, MonstrousMazeInput};
+
+const WALL_CHARACTER: char = '#';
+const BORDER_CHARACTER: char = '|';
+
+/// CSV header matching [`BenchCase::csv_line`]
+pub const CSV_HEADER: &str =
+    "challenge,complexity,width,height,monster_density,sample,seconds,valid";
+
+/// One challenge to time, with the parameters it was generated from
+#[derive(Debug, Clone)]
+pub struct BenchCase {
+    pub challenge: Challenge,
+    /// MD5HashCash only
+    pub complexity: Option<u32>,
+    /// MonstrousMaze only, without the borders
+    pub size: Option<(usize, usize)>,
+    /// MonstrousMaze only
+    pub monster_density: Option<f64>,
+    pub sample: u32,
+}
+
+impl BenchCase {
+    pub fn challenge_name(&self) -> &'static str {
+        match self.challenge {
+            Challenge::MD5HashCash(_) => "MD5HashCash",
+            Challenge::MonstrousMaze(_) => "MonstrousMaze",
+        }
+    }
+
+    /// The case and its measure as a line of the CSV, parameters that do not apply to the
+    /// challenge left empty
+    pub fn csv_line(&self, seconds: f64, valid: bool) -> String {
+        format!(
+            "{},{},{},{},{},{},{:.6},{}",
+            self.challenge_name(),
+            self.complexity.map_or(String::new(), |c| c.to_string()),
+            self.size
+                .map_or(String::new(), |(width, _)| width.to_string()),
+            self.size
+                .map_or(String::new(), |(_, height)| height.to_string()),
+            self.monster_density
+                .map_or(String::new(), |density| density.to_string()),
+            self.sample,
+            seconds,
+            valid
+        )
+    }
+
+    /// Parameters of the case, e.g. `complexity 12` or `7x5 monsters 0.1`
+    pub fn label(&self) -> String {
+        match (self.complexity, self.size, self.monster_density) {
+            (Some(complexity), _, _) => format!("complexity {}", complexity),
+            (_, Some((width, height)), Some(density)) => {
+                format!("{}x{} monsters {}", width, height, density)
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+/// `samples` messages for each complexity from 0 to `max_complexity`
+pub fn md5_hash_cash_cases(max_complexity: u32, samples: u32) -> Vec<BenchCase> {
+    let mut cases = vec![];
+    for complexity in 0..=max_complexity {
+        for sample in 0..samples {
+            cases.push(BenchCase {
+                challenge: Challenge::MD5HashCash(MD5HashCashInput {
+                    complexity,
+                    message: format!("sample-{}", sample),
+                }),
+                complexity: Some(complexity),
+                size: None,
+                monster_density: None,
+                sample,
+            });
+        }
+    }
+    cases
+}
+
+/// `samples` solvable mazes for each size and monster density, the same ones for a
+/// given `seed`
+pub fn monstrous_maze_cases(
+    sizes: &[(usize, usize)],
+    monster_densities: &[f64],
+    wall_density: f64,
+    samples: u32,
+    seed: u64,
+) -> Vec<BenchCase> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut cases = vec![];
+    for &(width, height) in sizes {
+        for &monster_density in monster_densities {
+            for sample in 0..samples {
+                cases.push(BenchCase {
+                    challenge: Challenge::MonstrousMaze(generate_maze(
+                        width,
+                        height,
+                        wall_density,
+                        monster_density,
+                        &mut rng,
+                    )),
+                    complexity: None,
+                    size: Some((width, height)),
+                    monster_density: Some(monster_density),
+                    sample,
+                });
+            }
+        }
+    }
+    cases
+}
+
+/// A `width` x `height` maze from the top left to the bottom right corner.
+///
+/// A random path going right and down is kept clear of walls, and the endurance covers
+/// the monsters on it, so the maze always has a solution.
+pub fn generate_maze(
+    width: usize,
+    height: usize,
+    wall_density: f64,
+    monster_density: f64,
+    rng: &mut impl Rng,
+) -> MonstrousMazeInput {
+    let width = width.max(2);
+    let height = height.max(1);
+    let mut on_path = vec![vec![false; width]; height];
+    let (mut line, mut column) = (0, 0);
+    on_path[line][column] = true;
+    while (line, column) != (height - 1, width - 1) {
+        if column == width - 1 || (line < height - 1 && rng.gen_bool(0.5)) {
+            line += 1;
+        } else {
+            column += 1;
+        }
+        on_path[line][column] = true;
+    }
+
+    let mut monsters_on_path = 0;
+    let mut lines = vec![];
+    for (line, on_path) in on_path.iter().enumerate() {
+        let mut cells = String::from(BORDER_CHARACTER);
+        for (column, &on_path) in on_path.iter().enumerate() {
+            let cell = if (line, column) == (0, 0) {
+                START_CHARACTER
+            } else if (line, column) == (height - 1, width - 1) {
+                END_CHARACTER
+            } else if rng.gen_bool(monster_density) {
+                if on_path {
+                    monsters_on_path += 1;
+                }
+                MONSTER_CHARACTER
+            } else if !on_path && rng.gen_bool(wall_density) {
+                WALL_CHARACTER
+            } else {
+                FREE_WAY_CHARACTER
+            };
+            cells.push(cell);
+        }
+        cells.push(BORDER_CHARACTER);
+        lines.push(cells);
+    }
+    MonstrousMazeInput {
+        grid: lines.join("\n"),
+        endurance: (monsters_on_path + 1).max(2),
+    }
+}
+
+/// Parses a maze size such as `7x5`
+pub fn parse_size(size: &str) -> Result<(usize, usize), String> {
+    let (width, height) = size
+        .split_once('x')
+        .ok_or_else(|| format!("{} is not a size like 7x5", size))?;
+    let width = width
+        .parse()
+        .map_err(|err| format!("Invalid width in {}: {}", size, err))?;
+    let height = height
+        .parse()
+        .map_err(|err| format!("Invalid height in {}: {}", size, err))?;
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod cases_tests {
+    use crate::cases::{generate_maze, md5_hash_cash_cases, monstrous_maze_cases, parse_size};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use shared::challenges::{solve_challenge, verify_answer};
+    use shared::Challenge;
+
+    #[test]
+    fn generated_mazes_should_always_be_solvable() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let input = generate_maze(5, 4, 0.3, 0.3, &mut rng);
+            let lines: Vec<&str> = input.grid.lines().collect();
+            assert_eq!(lines.len(), 4);
+            assert!(lines.iter().all(|line| line.chars().count() == 7));
+            assert!(lines[0].starts_with("|I"));
+            assert!(lines[3].ends_with("X|"));
+
+            let challenge = Challenge::MonstrousMaze(input);
+            assert!(verify_answer(&challenge, &solve_challenge(&challenge)));
+        }
+    }
+
+    #[test]
+    fn cases_should_cover_every_parameter_and_be_reproducible() {
+        let hash_cash = md5_hash_cash_cases(3, 2);
+        assert_eq!(hash_cash.len(), 8);
+        assert_eq!(
+            hash_cash[7].csv_line(0.5, true),
+            "MD5HashCash,3,,,,1,0.500000,true"
+        );
+
+        let mazes = monstrous_maze_cases(&[(4, 3), (5, 4)], &[0.0, 0.2], 0.3, 3, 42);
+        assert_eq!(mazes.len(), 12);
+        assert_eq!(
+            mazes[11].csv_line(0.25, false),
+            "MonstrousMaze,,5,4,0.2,2,0.250000,false"
+        );
+        let again = monstrous_maze_cases(&[(4, 3), (5, 4)], &[0.0, 0.2], 0.3, 3, 42);
+        for (maze, maze_again) in mazes.iter().zip(again.iter()) {
+            match (&maze.challenge, &maze_again.challenge) {
+                (Challenge::MonstrousMaze(a), Challenge::MonstrousMaze(b)) => {
+                    assert_eq!(a.grid, b.grid)
+                }
+                _ => panic!("Not a maze"),
+            }
+        }
+        assert_eq!(parse_size("7x5"), Ok((7, 5)));
+        assert!(parse_size("7").is_err());
+    }
+}
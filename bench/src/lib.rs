@@ -0,0 +1,107 @@
+pub mod cases;
+
+use crate::cases::BenchCase;
+use clap::Parser;
+use shared::challenges;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// CSV file the measures are written to
+    #[clap(long, value_parser, default_value = "bench.csv")]
+    output: PathBuf,
+    /// MD5HashCash is timed for every complexity from 0 to this one
+    #[clap(long, value_parser, default_value_t = 16)]
+    max_complexity: u32,
+    /// MonstrousMaze sizes, without the borders
+    #[clap(long, value_parser = cases::parse_size, value_delimiter = ',', default_value = "4x3,6x4,8x5,10x6")]
+    sizes: Vec<(usize, usize)>,
+    /// Share of the cells holding a monster
+    #[clap(long, value_parser, value_delimiter = ',', default_value = "0,0.1,0.2")]
+    monster_densities: Vec<f64>,
+    /// Share of the cells off the solution path holding a wall
+    #[clap(long, value_parser, default_value_t = 0.3)]
+    wall_density: f64,
+    /// Challenges timed for each set of parameters
+    #[clap(long, value_parser, default_value_t = 3)]
+    samples: u32,
+    /// Seed of the generated mazes
+    #[clap(long, value_parser, default_value_t = 42)]
+    seed: u64,
+    /// Passed by `cargo bench`, ignored
+    #[clap(long = "bench", value_parser, hide = true)]
+    _bench: bool,
+}
+
+/// Times every case chosen on the command line, shared by `patate-bench` and
+/// `cargo bench`
+pub fn main() {
+    let args = Args::parse();
+    if let Some(density) = args
+        .monster_densities
+        .iter()
+        .chain([args.wall_density].iter())
+        .find(|density| !(0.0..=1.0).contains(*density))
+    {
+        eprintln!("Densities are between 0 and 1, not {}", density);
+        process::exit(1);
+    }
+
+    let mut cases = cases::md5_hash_cash_cases(args.max_complexity, args.samples);
+    cases.extend(cases::monstrous_maze_cases(
+        &args.sizes,
+        &args.monster_densities,
+        args.wall_density,
+        args.samples,
+        args.seed,
+    ));
+
+    let file = match File::create(&args.output) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Cannot create {}: {}", args.output.display(), err);
+            process::exit(1);
+        }
+    };
+    if let Err(err) = run(&cases, args.samples, &mut BufWriter::new(file)) {
+        eprintln!("Cannot write {}: {}", args.output.display(), err);
+        process::exit(1);
+    }
+    println!("Measures written to {}", args.output.display());
+}
+
+/// Solves and verifies every case, writing one CSV line each and printing the mean time
+/// of each set of parameters
+fn run(cases: &[BenchCase], samples: u32, csv: &mut impl Write) -> std::io::Result<()> {
+    writeln!(csv, "{}", cases::CSV_HEADER)?;
+    let mut total_seconds = 0.0;
+    let mut all_valid = true;
+    for case in cases.iter() {
+        let started = Instant::now();
+        let answer = challenges::solve_challenge(&case.challenge);
+        let seconds = started.elapsed().as_secs_f64();
+        let valid = challenges::verify_answer(&case.challenge, &answer);
+        writeln!(csv, "{}", case.csv_line(seconds, valid))?;
+        csv.flush()?;
+
+        total_seconds += seconds;
+        all_valid &= valid;
+        if case.sample + 1 == samples {
+            println!(
+                "{:<14} {:<24} {:>10.6}s mean{}",
+                case.challenge_name(),
+                case.label(),
+                total_seconds / samples as f64,
+                if all_valid { "" } else { ", invalid answers" }
+            );
+            total_seconds = 0.0;
+            all_valid = true;
+        }
+    }
+    Ok(())
+}
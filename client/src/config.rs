@@ -0,0 +1,182 @@
+use crate::effort::DEFAULT_GIVE_UP_PERCENT;
+use crate::faults::Fault;
+use crate::strategy::Strategy;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the configured player name
+pub const NAME_VARIABLE: &str = "PATATE_NAME";
+/// Environment variable overriding the configured server address
+pub const ADDR_VARIABLE: &str = "PATATE_ADDR";
+
+/// Settings of the client, merged by increasing priority from the defaults, the JSON file
+/// given with `--config`, the environment and the command line.
+///
+/// The file may set any subset of the fields, with the names of the command line options
+/// in snake case.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+    pub name: String,
+    pub addr: String,
+    pub name_retries: u32,
+    pub room: Option<String>,
+    pub transcript: Option<PathBuf>,
+    pub strategy: Strategy,
+    pub faults: Vec<Fault>,
+    pub timeout_delay: u64,
+    pub reconnect_attempts: u32,
+    pub reconnect_delay_ms: u64,
+    pub threads: Option<usize>,
+    pub give_up_percent: u32,
+    pub tui: bool,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            name: "free_potato".to_string(),
+            addr: "localhost:7878".to_string(),
+            name_retries: 0,
+            room: None,
+            transcript: None,
+            strategy: Strategy::Random,
+            faults: Vec::new(),
+            timeout_delay: 6,
+            reconnect_attempts: 3,
+            reconnect_delay_ms: 500,
+            threads: None,
+            give_up_percent: DEFAULT_GIVE_UP_PERCENT,
+            tui: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file could not be read
+    Io(PathBuf, std::io::Error),
+    /// The config file is not a JSON object of known settings
+    Json(PathBuf, serde_json::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "Cannot read {}: {}", path.display(), err),
+            ConfigError::Json(path, err) => write!(f, "Invalid config {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ClientConfig {
+    /// Defaults overridden by the settings of the JSON file at `path`
+    pub fn from_file(path: &Path) -> Result<ClientConfig, ConfigError> {
+        let content =
+            fs::read_to_string(path).map_err(|err| ConfigError::Io(path.to_path_buf(), err))?;
+        serde_json::from_str(&content).map_err(|err| ConfigError::Json(path.to_path_buf(), err))
+    }
+
+    /// Overrides the name and the address with `PATATE_NAME` and `PATATE_ADDR`, looked up
+    /// with `variable`. Empty variables are ignored.
+    pub fn apply_env(&mut self, variable: impl Fn(&str) -> Option<String>) {
+        let variable = |key| variable(key).filter(|value: &String| !value.is_empty());
+        if let Some(name) = variable(NAME_VARIABLE) {
+            self.name = name;
+        }
+        if let Some(addr) = variable(ADDR_VARIABLE) {
+            self.addr = addr;
+        }
+    }
+
+    /// Effective configuration, in the format of the config file
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("the client config is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use crate::config::{ClientConfig, ConfigError, ADDR_VARIABLE, NAME_VARIABLE};
+    use crate::faults::Fault;
+    use crate::strategy::Strategy;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Writes `content` to a config file of its own in the temporary directory
+    fn config_file(test: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "patate-config-{}-{}.json",
+            test,
+            std::process::id()
+        ));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn file_should_override_only_the_settings_it_sets() {
+        let path = config_file(
+            "partial",
+            r#"{"name": "dark_salad", "strategy": "likely-to-fail", "faults": ["wrong-hashcode"], "threads": 2}"#,
+        );
+        let config = ClientConfig::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            config,
+            ClientConfig {
+                name: "dark_salad".to_string(),
+                strategy: Strategy::LikelyToFail,
+                faults: vec![Fault::WrongHashcode],
+                threads: Some(2),
+                ..ClientConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_settings_should_be_rejected() {
+        let path = config_file("unknown", r#"{"nmae": "dark_salad"}"#);
+        let result = ClientConfig::from_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(ConfigError::Json(..))));
+        assert!(matches!(
+            ClientConfig::from_file(&PathBuf::from("/no/such/config.json")),
+            Err(ConfigError::Io(..))
+        ));
+    }
+
+    #[test]
+    fn environment_should_override_the_file() {
+        let mut config = ClientConfig {
+            name: "dark_salad".to_string(),
+            addr: "potato.example:7878".to_string(),
+            ..ClientConfig::default()
+        };
+        config.apply_env(|key| match key {
+            NAME_VARIABLE => Some("cold_soup".to_string()),
+            ADDR_VARIABLE => Some(String::new()),
+            _ => None,
+        });
+        assert_eq!(config.name, "cold_soup");
+        assert_eq!(config.addr, "potato.example:7878");
+    }
+
+    #[test]
+    fn printed_config_should_read_back_the_same() {
+        let config = ClientConfig {
+            room: Some("tournament".to_string()),
+            faults: vec![Fault::Timeout, Fault::Disconnect],
+            give_up_percent: 60,
+            ..ClientConfig::default()
+        };
+        let path = config_file("round-trip", &config.to_json());
+        let read_back = ClientConfig::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read_back, config);
+    }
+}
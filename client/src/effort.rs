@@ -0,0 +1,455 @@
+use shared::challenges::hash_cash::MD5HashCash as MD5HashCashChallenge;
+use shared::challenges::monstrous_maze::{
+    Grid, MazeSearch, MonstrousMaze as MonstrousMazeChallenge,
+};
+use shared::challenges::{self, Challenge as _};
+use shared::Challenge::{MD5HashCash, MonstrousMaze};
+use shared::{
+    Challenge, ChallengeAnswer, ChallengeValue, MD5HashCashOutput, MonstrousMazeOutput,
+    RoundSummary,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Percentage of the estimated budget spent solving before sending a best-effort answer,
+/// unless the client config says otherwise
+pub const DEFAULT_GIVE_UP_PERCENT: u32 = 80;
+/// Every core is used once our mean time per challenge exceeds this share of the budget
+const BUSY_SHARE: f64 = 0.25;
+/// Seeds hashed by a worker between two looks at the deadline
+const SEEDS_PER_CHECK: u64 = 1024;
+
+/// Estimate of the time the server allows per challenge, which it keeps secret
+#[derive(Debug, Default)]
+pub struct RoundBudget {
+    /// Longest answer reported with its time: the budget is at least this long
+    lower_bound: f64,
+    /// Time left to the timed out player of each round ended by a timeout: the budget is
+    /// at most each of them
+    timed_out_rounds: Vec<f64>,
+    round_start: Option<Instant>,
+    limits: SolverLimits,
+}
+
+/// Solver settings of the client config
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolverLimits {
+    /// Threads searching MD5HashCash seeds once we are busy
+    pub busy_threads: usize,
+    /// Share of the estimated budget spent before giving up
+    pub give_up_ratio: f64,
+}
+
+impl SolverLimits {
+    /// `threads` once busy, every core if `None`, and `give_up_percent` of the budget
+    pub fn new(threads: Option<usize>, give_up_percent: u32) -> SolverLimits {
+        SolverLimits {
+            busy_threads: threads
+                .unwrap_or_else(|| {
+                    thread::available_parallelism().map_or(1, |threads| threads.get())
+                })
+                .max(1),
+            give_up_ratio: f64::from(give_up_percent) / 100.0,
+        }
+    }
+}
+
+impl Default for SolverLimits {
+    fn default() -> Self {
+        SolverLimits::new(None, DEFAULT_GIVE_UP_PERCENT)
+    }
+}
+
+impl RoundBudget {
+    pub fn new(limits: SolverLimits) -> RoundBudget {
+        RoundBudget {
+            limits,
+            ..RoundBudget::default()
+        }
+    }
+
+    /// A round starts `at`, when the leader board or the summary of the last round arrives
+    pub fn start_round(&mut self, at: Instant) {
+        self.round_start = Some(at);
+    }
+
+    /// Learns from the summary of a round received `at`, which also starts the next one
+    pub fn observe(&mut self, round_summary: &RoundSummary, at: Instant) {
+        let mut used_before = 0.0;
+        for reported in round_summary.chain.iter() {
+            match &reported.value {
+                ChallengeValue::Ok { used_time, .. }
+                | ChallengeValue::BadResult { used_time, .. } => {
+                    self.lower_bound = self.lower_bound.max(*used_time);
+                    used_before += used_time;
+                }
+                ChallengeValue::Timeout => {
+                    if let Some(round_start) = self.round_start {
+                        let elapsed = at.duration_since(round_start).as_secs_f64();
+                        self.timed_out_rounds.push(elapsed - used_before);
+                    }
+                }
+                ChallengeValue::Unreachable => {}
+            }
+        }
+        self.start_round(at);
+    }
+
+    /// Seconds allowed per challenge, `None` until a timeout shows that there is a limit
+    pub fn estimate(&self) -> Option<f64> {
+        self.timed_out_rounds
+            .iter()
+            .copied()
+            .min_by(f64::total_cmp)
+            .map(|upper_bound| upper_bound.max(self.lower_bound))
+    }
+
+    /// How hard to work on the next challenge, given our own mean time per challenge
+    pub fn effort(&self, own_mean_used_time: Option<f64>) -> Effort {
+        let budget = match self.estimate() {
+            Some(budget) => budget,
+            None => return Effort::default(),
+        };
+        let busy = own_mean_used_time.is_some_and(|mean| mean > budget * BUSY_SHARE);
+        Effort {
+            threads: if busy { self.limits.busy_threads } else { 1 },
+            give_up_after: Some(Duration::from_secs_f64(budget * self.limits.give_up_ratio)),
+        }
+    }
+}
+
+/// Resources given to the solver for one challenge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Effort {
+    /// Threads searching MD5HashCash seeds
+    pub threads: usize,
+    /// Time after which the best answer found so far is sent, `None` to search until solved
+    pub give_up_after: Option<Duration>,
+}
+
+impl Default for Effort {
+    fn default() -> Self {
+        Effort {
+            threads: 1,
+            give_up_after: None,
+        }
+    }
+}
+
+impl Effort {
+    /// Solves `challenge`, received `received_at`, or gives a best-effort answer in time.
+    ///
+    /// The seeds hashed or the maze states explored are counted in `tried` as they go.
+    pub fn solve(
+        &self,
+        challenge: Challenge,
+        received_at: Instant,
+        tried: &Arc<AtomicU64>,
+    ) -> ChallengeAnswer {
+        let deadline = self
+            .give_up_after
+            .map(|give_up_after| received_at + give_up_after);
+        match challenge {
+            MD5HashCash(md5_hash_cash_input) => ChallengeAnswer::MD5HashCash(solve_md5_hash_cash(
+                &MD5HashCashChallenge::new(md5_hash_cash_input),
+                self.threads.max(1) as u64,
+                deadline,
+                tried,
+            )),
+            MonstrousMaze(monstrous_maze_input) => {
+                ChallengeAnswer::MonstrousMaze(solve_monstrous_maze(
+                    &MonstrousMazeChallenge::new(monstrous_maze_input),
+                    deadline,
+                    tried,
+                ))
+            }
+        }
+    }
+}
+
+/// Worker `n` of `threads` tries the seeds `n + 1`, `n + 1 + threads`, …
+fn solve_md5_hash_cash(
+    challenge: &MD5HashCashChallenge,
+    threads: u64,
+    deadline: Option<Instant>,
+    tried: &AtomicU64,
+) -> MD5HashCashOutput {
+    let smallest_found = AtomicU64::new(u64::MAX);
+    let solutions: Vec<Option<MD5HashCashOutput>> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| {
+                let smallest_found = &smallest_found;
+                scope.spawn(move || {
+                    search_seeds(
+                        challenge,
+                        worker + 1,
+                        threads,
+                        deadline,
+                        smallest_found,
+                        tried,
+                    )
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("MD5HashCash worker panicked"))
+            .collect()
+    });
+    // Every seed below the smallest solution was tried, so the answer does not depend on
+    // the number of threads
+    let best = solutions
+        .into_iter()
+        .flatten()
+        .min_by_key(|solution| solution.seed);
+    match best {
+        Some(solution) => solution,
+        None => {
+            println!("/!\\ Giving up MD5HashCash before the server timeout /!\\");
+            MD5HashCashOutput {
+                seed: 0,
+                hashcode: challenge.hashcode(0),
+            }
+        }
+    }
+}
+
+fn search_seeds(
+    challenge: &MD5HashCashChallenge,
+    first_seed: u64,
+    step: u64,
+    deadline: Option<Instant>,
+    smallest_found: &AtomicU64,
+    tried: &AtomicU64,
+) -> Option<MD5HashCashOutput> {
+    let mut seed = first_seed;
+    for hashed in 0_u64.. {
+        // Seeds above a solution found by any worker cannot be the smallest one
+        if seed > smallest_found.load(Ordering::Relaxed) {
+            return None;
+        }
+        if hashed % SEEDS_PER_CHECK == 0 {
+            if hashed > 0 {
+                tried.fetch_add(SEEDS_PER_CHECK, Ordering::Relaxed);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return None;
+            }
+        }
+        let hashcode = challenge.hashcode(seed);
+        let hashcode_binary = challenges::convert_string_to_binary(hashcode.clone());
+        if challenges::check_number_of_zero(hashcode_binary, challenge.input.complexity) {
+            smallest_found.fetch_min(seed, Ordering::Relaxed);
+            tried.fetch_add(hashed % SEEDS_PER_CHECK + 1, Ordering::Relaxed);
+            return Some(MD5HashCashOutput { seed, hashcode });
+        }
+        seed += step;
+    }
+    None
+}
+
+/// Without a deadline every path is explored. With one, the search deepens step by step so
+/// that a short path is found before the deadline, and an empty path is sent otherwise.
+fn solve_monstrous_maze(
+    challenge: &MonstrousMazeChallenge,
+    deadline: Option<Instant>,
+    explored: &Arc<AtomicU64>,
+) -> MonstrousMazeOutput {
+    let mut search = MazeSearch {
+        explored: Arc::clone(explored),
+        ..MazeSearch::default()
+    };
+    let deadline = match deadline {
+        Some(deadline) => deadline,
+        None => return challenge.solve_within(&search),
+    };
+    search.deadline = Some(deadline);
+    let grid = Grid::new(challenge.input.clone());
+    let cells = challenge.input.grid.len();
+    search.max_path_length =
+        (grid.start.0.abs_diff(grid.end.0) + grid.start.1.abs_diff(grid.end.1)).max(1) as usize;
+    loop {
+        let output = challenge.solve_within(&search);
+        if !output.path.is_empty() || search.max_path_length >= cells || Instant::now() >= deadline
+        {
+            return output;
+        }
+        search.max_path_length *= 2;
+    }
+}
+
+#[cfg(test)]
+mod effort_tests {
+    use crate::effort::{Effort, RoundBudget, SolverLimits};
+    use shared::challenges::hash_cash::MD5HashCash;
+    use shared::challenges::monstrous_maze::MonstrousMaze;
+    use shared::challenges::Challenge as _;
+    use shared::{
+        Challenge, ChallengeAnswer, ChallengeValue, MD5HashCashInput, MonstrousMazeInput,
+        ReportedChallengeResult, RoundSummary,
+    };
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn summary(chain: Vec<(&str, ChallengeValue)>) -> RoundSummary {
+        RoundSummary {
+            challenge: "MD5HashCash".to_string(),
+            chain: chain
+                .into_iter()
+                .map(|(name, value)| ReportedChallengeResult {
+                    name: name.to_string(),
+                    value,
+                })
+                .collect(),
+        }
+    }
+
+    fn ok(used_time: f64) -> ChallengeValue {
+        ChallengeValue::Ok {
+            used_time,
+            next_target: "free_potato".to_string(),
+        }
+    }
+
+    fn md5_input() -> MD5HashCashInput {
+        MD5HashCashInput {
+            complexity: 9,
+            message: "hello".to_string(),
+        }
+    }
+
+    #[test]
+    fn budget_should_be_unknown_until_a_timeout() {
+        let start = Instant::now();
+        let mut budget = RoundBudget::default();
+        budget.start_round(start);
+        budget.observe(
+            &summary(vec![("dark_salad", ok(1.5))]),
+            start + Duration::from_secs(2),
+        );
+        assert_eq!(budget.estimate(), None);
+        assert_eq!(budget.effort(Some(10.0)), Effort::default());
+    }
+
+    #[test]
+    fn timed_out_rounds_should_bound_the_budget() {
+        let start = Instant::now();
+        let mut budget = RoundBudget::default();
+        budget.start_round(start);
+        // 6 seconds, 1 of them used by dark_salad before cold_soup timed out
+        budget.observe(
+            &summary(vec![
+                ("dark_salad", ok(1.0)),
+                ("cold_soup", ChallengeValue::Timeout),
+            ]),
+            start + Duration::from_secs(6),
+        );
+        assert_eq!(budget.estimate(), Some(5.0));
+        budget.observe(
+            &summary(vec![("cold_soup", ChallengeValue::Timeout)]),
+            start + Duration::from_secs(10),
+        );
+        assert_eq!(budget.estimate(), Some(4.0));
+        // An answer accepted after 4.5 seconds shows that the budget is longer
+        budget.observe(
+            &summary(vec![("dark_salad", ok(4.5))]),
+            start + Duration::from_secs(15),
+        );
+        assert_eq!(budget.estimate(), Some(4.5));
+
+        let relaxed = budget.effort(Some(0.1));
+        assert_eq!(relaxed.threads, 1);
+        assert_eq!(relaxed.give_up_after, Some(Duration::from_secs_f64(3.6)));
+        // 2 seconds per challenge is over a quarter of the budget: every core is used
+        let busy = budget.effort(Some(2.0));
+        assert_eq!(
+            busy.threads,
+            thread::available_parallelism().map_or(1, |threads| threads.get())
+        );
+        assert_eq!(busy.give_up_after, relaxed.give_up_after);
+    }
+
+    #[test]
+    fn solver_limits_should_set_the_threads_and_the_give_up_time() {
+        let start = Instant::now();
+        let mut budget = RoundBudget::new(SolverLimits::new(Some(3), 50));
+        budget.start_round(start);
+        budget.observe(
+            &summary(vec![("cold_soup", ChallengeValue::Timeout)]),
+            start + Duration::from_secs(4),
+        );
+        let busy = budget.effort(Some(2.0));
+        assert_eq!(busy.threads, 3);
+        assert_eq!(busy.give_up_after, Some(Duration::from_secs(2)));
+        assert_eq!(budget.effort(Some(0.1)).threads, 1);
+        assert_eq!(SolverLimits::new(Some(0), 50).busy_threads, 1);
+    }
+
+    #[test]
+    fn threads_should_find_the_smallest_valid_seed() {
+        let seed = |threads| {
+            let effort = Effort {
+                threads,
+                give_up_after: None,
+            };
+            let tried = Arc::new(AtomicU64::new(0));
+            let answer = effort.solve(Challenge::MD5HashCash(md5_input()), Instant::now(), &tried);
+            assert!(tried.load(Ordering::Relaxed) > 0);
+            match answer {
+                ChallengeAnswer::MD5HashCash(output) => {
+                    assert!(MD5HashCash::new(md5_input()).verify(&output));
+                    output.seed
+                }
+                _ => panic!("MD5HashCash answer expected"),
+            }
+        };
+        let sequential = seed(1);
+        for threads in [2, 3, 4] {
+            assert_eq!(seed(threads), sequential);
+        }
+    }
+
+    #[test]
+    fn solver_should_give_up_at_the_deadline() {
+        let effort = Effort {
+            threads: 2,
+            give_up_after: Some(Duration::ZERO),
+        };
+        let impossible = MD5HashCashInput {
+            complexity: 128,
+            message: "hello".to_string(),
+        };
+        let started = Instant::now();
+        let answer = effort.solve(Challenge::MD5HashCash(impossible), started, &Arc::default());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(matches!(answer, ChallengeAnswer::MD5HashCash(..)));
+    }
+
+    #[test]
+    fn maze_search_should_deepen_until_a_path_is_found() {
+        let maze_input = MonstrousMazeInput {
+            endurance: 2,
+            grid: "|I M  |\n|     X|".to_string(),
+        };
+        let effort = Effort {
+            threads: 1,
+            give_up_after: Some(Duration::from_secs(10)),
+        };
+        let explored = Arc::new(AtomicU64::new(0));
+        match effort.solve(
+            Challenge::MonstrousMaze(maze_input.clone()),
+            Instant::now(),
+            &explored,
+        ) {
+            ChallengeAnswer::MonstrousMaze(output) => {
+                assert!(MonstrousMaze::new(maze_input).verify(&output))
+            }
+            _ => panic!("MonstrousMaze answer expected"),
+        }
+        assert!(explored.load(Ordering::Relaxed) > 0);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::session::{ClientSession, SessionError};
+use crate::tui::Dashboard;
+use serde::{Deserialize, Serialize};
+use shared::{
+    Challenge, ChallengeAnswer, ChallengeResult, MD5HashCashOutput, Message, MonstrousMazeOutput,
+    MAX_MESSAGE_SIZE,
+};
+use std::thread;
+use std::time::Duration;
+
+/// Target no server accepts as a player name
+const UNKNOWN_TARGET: &str = "no such player";
+
+/// Misbehaviour injected by the client, to check that a server answers it with the
+/// documented `ChallengeValue` instead of crashing
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Fault {
+    /// MD5HashCash answer with the hashcode of another seed: `BadResult`
+    WrongHashcode,
+    /// MonstrousMaze answer with a path of invalid characters: `BadResult`
+    InvalidPath,
+    /// Answer to the other kind of challenge: `BadResult`
+    WrongChallenge,
+    /// Valid answer sent after the server timeout: `Timeout`
+    Timeout,
+    /// Valid answer targeting a player that does not exist: `Ok`, with a random next player
+    UnknownTarget,
+    /// Frame larger than the server accepts, instead of the answer: `Unreachable`
+    OversizedFrame,
+    /// Half of a length prefix, then the connection is closed: `Unreachable`
+    TruncatedPrefix,
+    /// Second connection subscribing with the same name: `AlreadyRegistered`
+    Reconnect,
+    /// Connection dropped instead of the answer: `Unreachable`, unless the client
+    /// reconnects within the server grace period and gets the challenge again
+    Disconnect,
+}
+
+impl Fault {
+    /// Whether the fault can be injected in the answer to `challenge`
+    fn applies_to(&self, challenge: &Challenge) -> bool {
+        match self {
+            Fault::WrongHashcode => matches!(challenge, Challenge::MD5HashCash(..)),
+            Fault::InvalidPath => matches!(challenge, Challenge::MonstrousMaze(..)),
+            Fault::Reconnect => false,
+            _ => true,
+        }
+    }
+}
+
+/// Faults left to inject, each in the answer to the first challenge it applies to
+#[derive(Debug, Default)]
+pub struct Faults {
+    pending: Vec<Fault>,
+    /// How long the `timeout` fault waits before answering
+    timeout_delay: Duration,
+}
+
+impl Faults {
+    pub fn new(faults: Vec<Fault>, timeout_delay: Duration) -> Faults {
+        Faults {
+            pending: faults,
+            timeout_delay,
+        }
+    }
+
+    /// Whether the session must check that its name cannot be taken a second time
+    pub fn reconnect(&self) -> bool {
+        self.pending.contains(&Fault::Reconnect)
+    }
+
+    /// Takes the next fault to inject in the answer to `challenge`, if any
+    pub fn take_for(&mut self, challenge: &Challenge) -> Option<Fault> {
+        let index = self
+            .pending
+            .iter()
+            .position(|fault| fault.applies_to(challenge))?;
+        Some(self.pending.remove(index))
+    }
+
+    /// Sends `result`, the honest answer to the challenge, broken by `fault`, which is
+    /// shown on the `dashboard`.
+    pub fn inject(
+        &self,
+        fault: Fault,
+        session: &ClientSession,
+        mut result: ChallengeResult,
+        dashboard: &Dashboard,
+    ) -> Result<(), SessionError> {
+        dashboard.connection(format!("Injecting fault {:?}", fault));
+        match fault {
+            Fault::WrongHashcode => {
+                if let ChallengeAnswer::MD5HashCash(output) = &mut result.answer {
+                    output.seed += 1;
+                }
+            }
+            Fault::InvalidPath => {
+                if let ChallengeAnswer::MonstrousMaze(output) = &mut result.answer {
+                    output.path.insert(0, '?');
+                }
+            }
+            Fault::WrongChallenge => {
+                result.answer = match result.answer {
+                    ChallengeAnswer::MD5HashCash(..) => {
+                        ChallengeAnswer::MonstrousMaze(MonstrousMazeOutput {
+                            path: ">".to_string(),
+                        })
+                    }
+                    ChallengeAnswer::MonstrousMaze(..) => {
+                        ChallengeAnswer::MD5HashCash(MD5HashCashOutput {
+                            seed: 0,
+                            hashcode: "0".repeat(32),
+                        })
+                    }
+                }
+            }
+            Fault::Timeout => thread::sleep(self.timeout_delay),
+            Fault::UnknownTarget => result.next_target = UNKNOWN_TARGET.to_string(),
+            Fault::OversizedFrame => {
+                let size = (MAX_MESSAGE_SIZE as u32 + 1).to_be_bytes();
+                session.send_raw(&size)?;
+                return session.send_raw(b"{}");
+            }
+            Fault::TruncatedPrefix => {
+                session.send_raw(&[0, 0])?;
+                return session.shutdown_write();
+            }
+            Fault::Disconnect => return session.disconnect(),
+            Fault::Reconnect => {}
+        }
+        session.send(Message::ChallengeResult(result))
+    }
+}
+
+/// Subscribes again as `name` on a second connection, which the server should refuse,
+/// and shows the outcome on the `dashboard`.
+pub fn reconnect(addr: &str, name: &str, room: Option<&str>, dashboard: &Dashboard) {
+    dashboard.connection(format!("Injecting fault {:?}", Fault::Reconnect));
+    match ClientSession::connect(addr, name, 0, room, None) {
+        Ok(session) => {
+            dashboard.connection(format!(
+                "The server accepted {} a second time",
+                session.name()
+            ));
+            if let Err(err) = session.close() {
+                dashboard.connection(format!("Cannot close the second connection: {}", err));
+            }
+        }
+        Err(err) => dashboard.connection(format!("Second connection refused: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod faults_tests {
+    use crate::effort::RoundBudget;
+    use crate::faults::{Fault, Faults, UNKNOWN_TARGET};
+    use crate::game::play;
+    use crate::mock_server::{MockServer, Step};
+    use crate::reconnect::Backoff;
+    use crate::session::ClientSession;
+    use crate::strategy::Strategy;
+    use crate::tui::Dashboard;
+    use shared::challenges::hash_cash::MD5HashCash;
+    use shared::challenges::monstrous_maze::MonstrousMaze;
+    use shared::challenges::Challenge as _;
+    use shared::{
+        Challenge, ChallengeAnswer, EndOfGame, MD5HashCashInput, Message, MonstrousMazeInput,
+        PublicLeaderBoard, SubscribeResult, Welcome,
+    };
+    use std::time::Duration;
+
+    fn md5_input() -> MD5HashCashInput {
+        MD5HashCashInput {
+            complexity: 9,
+            message: "hello".to_string(),
+        }
+    }
+
+    fn maze_input() -> MonstrousMazeInput {
+        MonstrousMazeInput {
+            endurance: 10,
+            grid: "|I   X|".to_string(),
+        }
+    }
+
+    #[test]
+    fn faults_should_wait_for_a_challenge_they_apply_to() {
+        let mut faults = Faults::new(
+            vec![Fault::WrongHashcode, Fault::Reconnect, Fault::Timeout],
+            Duration::ZERO,
+        );
+        let maze = Challenge::MonstrousMaze(maze_input());
+        let md5 = Challenge::MD5HashCash(md5_input());
+        assert!(faults.reconnect());
+        assert_eq!(faults.take_for(&maze), Some(Fault::Timeout));
+        assert_eq!(faults.take_for(&maze), None);
+        assert_eq!(faults.take_for(&md5), Some(Fault::WrongHashcode));
+        assert_eq!(faults.take_for(&md5), None);
+    }
+
+    #[test]
+    fn faulty_answers_should_be_sent_in_order() {
+        let script = vec![
+            Step::expect("Hello", |message| matches!(message, Message::Hello)),
+            Step::Send(Message::Welcome(Welcome { version: 1 })),
+            Step::expect("Subscribe", |message| {
+                matches!(message, Message::Subscribe(..))
+            }),
+            Step::Send(Message::SubscribeResult(SubscribeResult::Ok)),
+            Step::Send(Message::Challenge(Challenge::MD5HashCash(md5_input()))),
+            Step::expect("a wrong hashcode", |message| match message {
+                Message::ChallengeResult(result) => match &result.answer {
+                    ChallengeAnswer::MD5HashCash(output) => {
+                        !MD5HashCash::new(md5_input()).verify(output)
+                    }
+                    _ => false,
+                },
+                _ => false,
+            }),
+            Step::Send(Message::Challenge(Challenge::MonstrousMaze(maze_input()))),
+            Step::expect(
+                "a valid path to an unknown target",
+                |message| match message {
+                    Message::ChallengeResult(result) => match &result.answer {
+                        ChallengeAnswer::MonstrousMaze(output) => {
+                            result.next_target == UNKNOWN_TARGET
+                                && MonstrousMaze::new(maze_input()).verify(output)
+                        }
+                        _ => false,
+                    },
+                    _ => false,
+                },
+            ),
+            Step::Send(Message::Challenge(Challenge::MonstrousMaze(maze_input()))),
+            Step::expect("the answer to a MD5HashCash", |message| match message {
+                Message::ChallengeResult(result) => {
+                    matches!(result.answer, ChallengeAnswer::MD5HashCash(..))
+                }
+                _ => false,
+            }),
+            Step::Send(Message::EndOfGame(EndOfGame {
+                leader_board: PublicLeaderBoard(vec![]),
+            })),
+            Step::Close,
+            Step::ExpectClosed,
+        ];
+        let server = MockServer::start(script);
+
+        let session = ClientSession::connect(&server.addr(), "free_potato", 0, None, None).unwrap();
+        let faults = Faults::new(
+            vec![
+                Fault::WrongHashcode,
+                Fault::UnknownTarget,
+                Fault::WrongChallenge,
+            ],
+            Duration::ZERO,
+        );
+        play(
+            session,
+            Strategy::Random.build(),
+            faults,
+            Backoff::default(),
+            RoundBudget::default(),
+            &Dashboard::default(),
+        )
+        .unwrap();
+        server.finish().unwrap();
+    }
+}
@@ -0,0 +1,343 @@
+use crate::effort::RoundBudget;
+use crate::faults::Faults;
+use crate::opponents::OpponentModel;
+use crate::reconnect::Backoff;
+use crate::session::{ClientSession, SessionError};
+use crate::strategy::TargetStrategy;
+use crate::tui::Dashboard;
+use shared::{ChallengeResult, Message, PublicLeaderBoard};
+use std::time::Instant;
+
+/// Plays until `EndOfGame`, then closes the session and returns the final leader board.
+///
+/// The potato goes to the player chosen by `strategy`. Each of the `faults` is injected
+/// in the answer to the first challenge it applies to. The solver effort follows the time
+/// the server seems to allow per challenge, estimated by `budget`. A lost connection is
+/// resumed as `backoff` allows. Everything received is shown on the `dashboard`.
+pub fn play(
+    mut session: ClientSession,
+    mut strategy: Box<dyn TargetStrategy>,
+    mut faults: Faults,
+    backoff: Backoff,
+    mut budget: RoundBudget,
+    dashboard: &Dashboard,
+) -> Result<PublicLeaderBoard, SessionError> {
+    let mut public_leader_board = Vec::new();
+    let mut opponents = OpponentModel::default();
+    dashboard.connection(format!("Playing as {}", session.name()));
+    loop {
+        let message = match session.receive() {
+            Ok(message) => message,
+            Err(lost) => {
+                dashboard.connection(format!("Reconnecting as {}: {}", session.name(), lost));
+                let resumed = backoff.resume(&mut session, lost);
+                if let Err(err) = &resumed {
+                    dashboard.connection(format!("Disconnected: {}", err));
+                }
+                resumed?;
+                dashboard.connection(format!("Playing as {}", session.name()));
+                continue;
+            }
+        };
+        match message {
+            Message::PublicLeaderBoard(leader_board) => {
+                budget.start_round(Instant::now());
+                dashboard.leader_board(&leader_board.0);
+                public_leader_board = leader_board.0;
+            }
+            Message::Challenge(challenge) => {
+                let received_at = Instant::now();
+                dashboard.challenge(&challenge, received_at);
+                let fault = faults.take_for(&challenge);
+                let effort = budget.effort(opponents.stats(session.name()).mean_used_time());
+                if let Some(give_up_after) = effort.give_up_after {
+                    dashboard.connection(format!(
+                        "Solving with {} thread(s), giving up after {:.2}s",
+                        effort.threads,
+                        give_up_after.as_secs_f64()
+                    ));
+                }
+                let answer = effort.solve(challenge, received_at, &dashboard.tried);
+                dashboard.solved(Instant::now());
+                let result = ChallengeResult {
+                    answer,
+                    next_target: strategy.next_target(
+                        &public_leader_board,
+                        session.name(),
+                        &opponents,
+                    ),
+                };
+                let sent = match fault {
+                    Some(fault) => faults.inject(fault, &session, result, dashboard),
+                    None => session.send(Message::ChallengeResult(result)),
+                };
+                // A lost connection shows up on the next receive
+                if let Err(err) = sent {
+                    dashboard.connection(format!("Cannot send the answer: {}", err));
+                }
+            }
+            Message::RoundSummary(round_summary) => {
+                opponents.observe(&round_summary);
+                budget.observe(&round_summary, Instant::now());
+                dashboard.round_summary(&round_summary);
+            }
+            Message::EndOfGame(end_of_game) => {
+                dashboard.leader_board(&end_of_game.leader_board.0);
+                dashboard.connection(format!("Game over for {}", session.name()));
+                if let Err(err) = session.close() {
+                    eprintln!("{}", err);
+                }
+                return Ok(end_of_game.leader_board);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod game_tests {
+    use crate::effort::RoundBudget;
+    use crate::faults::Faults;
+    use crate::game::play;
+    use crate::mock_server::{MockServer, Step};
+    use crate::ranking::final_rank;
+    use crate::reconnect::Backoff;
+    use crate::session::{ClientSession, SessionError};
+    use crate::strategy::Strategy;
+    use crate::tui::Dashboard;
+    use shared::challenges::hash_cash::MD5HashCash as MD5HashCashChallenge;
+    use shared::challenges::monstrous_maze::MonstrousMaze as MonstrousMazeChallenge;
+    use shared::challenges::Challenge as _;
+    use shared::{
+        Challenge, ChallengeAnswer, ChallengeValue, EndOfGame, MD5HashCashInput,
+        MonstrousMazeInput, PublicLeaderBoard, PublicPlayer, ReportedChallengeResult, RoundSummary,
+    };
+    use shared::{Message, SubscribeError, SubscribeResult, Welcome};
+    use std::time::Duration;
+
+    fn player(name: &str, score: i32) -> PublicPlayer {
+        PublicPlayer {
+            name: name.to_string(),
+            stream_id: "127.0.0.1".to_string(),
+            score,
+            steps: 0,
+            is_active: true,
+            total_used_time: 0.0,
+        }
+    }
+
+    fn leader_board() -> Vec<PublicPlayer> {
+        vec![player("free_potato", 0), player("dark_salad", 0)]
+    }
+
+    fn md5_input() -> MD5HashCashInput {
+        MD5HashCashInput {
+            complexity: 9,
+            message: "hello".to_string(),
+        }
+    }
+
+    fn maze_input() -> MonstrousMazeInput {
+        MonstrousMazeInput {
+            endurance: 10,
+            grid: "|I   X|".to_string(),
+        }
+    }
+
+    fn connect(server: &MockServer) -> ClientSession {
+        ClientSession::connect(&server.addr(), "free_potato", 0, None, None).unwrap()
+    }
+
+    fn handshake() -> Vec<Step> {
+        vec![
+            Step::expect("Hello", |message| matches!(message, Message::Hello)),
+            Step::Send(Message::Welcome(Welcome { version: 1 })),
+            Step::expect("Subscribe as free_potato", |message| match message {
+                Message::Subscribe(subscribe) => subscribe.name == "free_potato",
+                _ => false,
+            }),
+            Step::Send(Message::SubscribeResult(SubscribeResult::Ok)),
+            Step::Send(Message::PublicLeaderBoard(
+                PublicLeaderBoard(leader_board()),
+            )),
+        ]
+    }
+
+    fn end_of_game(leader_board: Vec<PublicPlayer>) -> Vec<Step> {
+        vec![
+            Step::Send(Message::EndOfGame(EndOfGame {
+                leader_board: PublicLeaderBoard(leader_board),
+            })),
+            Step::Close,
+            Step::ExpectClosed,
+        ]
+    }
+
+    #[test]
+    fn client_should_play_until_the_end_of_game() {
+        let mut script = handshake();
+        script.extend([
+            Step::Send(Message::Challenge(Challenge::MD5HashCash(md5_input()))),
+            Step::expect("a valid MD5HashCash answer", |message| match message {
+                Message::ChallengeResult(result) => match &result.answer {
+                    ChallengeAnswer::MD5HashCash(output) => {
+                        MD5HashCashChallenge::new(md5_input()).verify(output)
+                    }
+                    _ => false,
+                },
+                _ => false,
+            }),
+            Step::Send(Message::RoundSummary(RoundSummary {
+                challenge: "MD5HashCash".to_string(),
+                chain: vec![ReportedChallengeResult {
+                    name: "free_potato".to_string(),
+                    value: ChallengeValue::Ok {
+                        used_time: 0.1,
+                        next_target: "dark_salad".to_string(),
+                    },
+                }],
+            })),
+            Step::Send(Message::Challenge(Challenge::MonstrousMaze(maze_input()))),
+            Step::expect("a valid MonstrousMaze answer", |message| match message {
+                Message::ChallengeResult(result) => match &result.answer {
+                    ChallengeAnswer::MonstrousMaze(output) => {
+                        MonstrousMazeChallenge::new(maze_input()).verify(output)
+                    }
+                    _ => false,
+                },
+                _ => false,
+            }),
+        ]);
+        script.extend(end_of_game(vec![
+            player("dark_salad", 0),
+            player("free_potato", -1),
+        ]));
+        let server = MockServer::start(script);
+
+        let session = connect(&server);
+        let leader_board = play(
+            session,
+            Strategy::Random.build(),
+            Faults::default(),
+            Backoff::default(),
+            RoundBudget::default(),
+            &Dashboard::default(),
+        )
+        .unwrap();
+        server.finish().unwrap();
+        assert_eq!(final_rank(&leader_board.0, "free_potato"), Some(2));
+    }
+
+    #[test]
+    fn next_target_should_be_a_player_of_the_leader_board() {
+        let mut script = handshake();
+        script.extend([
+            Step::Send(Message::Challenge(Challenge::MonstrousMaze(maze_input()))),
+            Step::expect("a next target from the leader board", |message| {
+                matches!(message, Message::ChallengeResult(result)
+                    if ["free_potato", "dark_salad"].contains(&result.next_target.as_str()))
+            }),
+        ]);
+        script.extend(end_of_game(leader_board()));
+        let server = MockServer::start(script);
+
+        let session = connect(&server);
+        play(
+            session,
+            Strategy::Random.build(),
+            Faults::default(),
+            Backoff::default(),
+            RoundBudget::default(),
+            &Dashboard::default(),
+        )
+        .unwrap();
+        server.finish().unwrap();
+    }
+
+    /// Handshake of a reconnection, answered with `subscribe_result`
+    fn rehandshake(subscribe_result: SubscribeResult) -> Vec<Step> {
+        vec![
+            Step::expect("Hello", |message| matches!(message, Message::Hello)),
+            Step::Send(Message::Welcome(Welcome { version: 1 })),
+            Step::expect("Subscribe again as free_potato", |message| match message {
+                Message::Subscribe(subscribe) => subscribe.name == "free_potato",
+                _ => false,
+            }),
+            Step::Send(Message::SubscribeResult(subscribe_result)),
+        ]
+    }
+
+    #[test]
+    fn lost_connection_should_be_resumed_under_the_same_name() {
+        let mut lost = handshake();
+        lost.push(Step::Close);
+        let mut resumed = rehandshake(SubscribeResult::Ok);
+        resumed.extend([
+            Step::Send(Message::Challenge(Challenge::MD5HashCash(md5_input()))),
+            Step::expect("a valid MD5HashCash answer", |message| match message {
+                Message::ChallengeResult(result) => match &result.answer {
+                    ChallengeAnswer::MD5HashCash(output) => {
+                        MD5HashCashChallenge::new(md5_input()).verify(output)
+                    }
+                    _ => false,
+                },
+                _ => false,
+            }),
+        ]);
+        resumed.extend(end_of_game(leader_board()));
+        let server = MockServer::start_sessions(vec![lost, resumed]);
+
+        let session = connect(&server);
+        let backoff = Backoff::new(3, Duration::ZERO);
+        play(
+            session,
+            Strategy::Random.build(),
+            Faults::default(),
+            backoff,
+            RoundBudget::default(),
+            &Dashboard::default(),
+        )
+        .unwrap();
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn refused_reconnection_should_not_be_retried() {
+        let mut lost = handshake();
+        lost.push(Step::Close);
+        let refused = rehandshake(SubscribeResult::Err(SubscribeError::AlreadyRegistered));
+        let server = MockServer::start_sessions(vec![lost, refused]);
+
+        let session = connect(&server);
+        let backoff = Backoff::new(3, Duration::ZERO);
+        let result = play(
+            session,
+            Strategy::Random.build(),
+            Faults::default(),
+            backoff,
+            RoundBudget::default(),
+            &Dashboard::default(),
+        );
+        server.finish().unwrap();
+        assert!(matches!(result, Err(SessionError::AlreadyRegistered(..))));
+    }
+
+    #[test]
+    fn lost_connection_should_be_an_error() {
+        let mut script = handshake();
+        script.push(Step::Close);
+        let server = MockServer::start(script);
+
+        let session = connect(&server);
+        assert!(play(
+            session,
+            Strategy::Random.build(),
+            Faults::default(),
+            Backoff::default(),
+            RoundBudget::default(),
+            &Dashboard::default()
+        )
+        .is_err());
+        server.finish().unwrap();
+    }
+}
@@ -1,104 +1,226 @@
+mod config;
+mod effort;
+mod faults;
+mod game;
+#[cfg(test)]
+mod mock_server;
+mod opponents;
+mod ranking;
+mod reconnect;
+mod session;
+mod strategy;
+mod tui;
+
 use clap::Parser;
-use rand::Rng;
-use shared::challenges::hash_cash::MD5HashCash as MD5HashCashChallenge;
-use shared::challenges::monstrous_maze::MonstrousMaze as MonstrousMazeChallenge;
-use shared::challenges::Challenge;
-use shared::Challenge::{MD5HashCash, MonstrousMaze};
-use shared::{ChallengeAnswer, ChallengeResult, Message, PublicPlayer, Subscribe, SubscribeResult};
+use config::{ClientConfig, ConfigError};
+use effort::{RoundBudget, SolverLimits};
+use faults::{Fault, Faults};
+use reconnect::Backoff;
+use session::ClientSession;
+use shared::transcript::Transcript;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use strategy::Strategy;
+use tui::Dashboard;
 
+// Every option but `--config` and `--print-config` overrides the config file and the
+// environment, see `ClientConfig`
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    #[clap(
-        short = 'n',
-        long,
-        required = false,
-        default_value = "free_potato",
-        value_parser
-    )]
-    name: String,
-    #[clap(
-        short = 'a',
-        long,
-        required = false,
-        default_value = "localhost:7878",
-        value_parser
-    )]
-    addr: String,
+    /// JSON file with the settings not given on the command line
+    #[clap(long, value_parser)]
+    config: Option<PathBuf>,
+    /// Print the effective configuration as JSON and exit
+    #[clap(long, value_parser)]
+    print_config: bool,
+    /// Player name, also read from `PATATE_NAME` [default: free_potato]
+    #[clap(short = 'n', long, value_parser)]
+    name: Option<String>,
+    /// Server address, also read from `PATATE_ADDR` [default: localhost:7878]
+    #[clap(short = 'a', long, value_parser)]
+    addr: Option<String>,
+    /// Number of suffixed names (`name-2`, `name-3`, …) to try when the name is already
+    /// registered [default: 0]
+    #[clap(long, value_parser)]
+    name_retries: Option<u32>,
+    /// Game room to play in, instead of the server default one
+    #[clap(short = 'r', long, value_parser)]
+    room: Option<String>,
+    /// JSON Lines file where every message sent and received is recorded
+    #[clap(long, value_parser)]
+    transcript: Option<PathBuf>,
+    /// How to choose the player who receives the potato [default: random]
+    #[clap(long, value_enum)]
+    strategy: Option<Strategy>,
+    /// Misbehaviour to inject, to check how the server copes with it (can be repeated)
+    #[clap(long = "fault", value_enum)]
+    faults: Vec<Fault>,
+    /// Seconds the `timeout` fault waits before answering, beyond the server timeout
+    /// [default: 6]
+    #[clap(long, value_parser)]
+    timeout_delay: Option<u64>,
+    /// Number of reconnections tried after losing the connection during the game
+    /// [default: 3]
+    #[clap(long, value_parser)]
+    reconnect_attempts: Option<u32>,
+    /// Milliseconds before the first reconnection, doubled before each next one
+    /// [default: 500]
+    #[clap(long, value_parser)]
+    reconnect_delay_ms: Option<u64>,
+    /// Threads searching MD5HashCash seeds once our mean time per challenge is over a
+    /// quarter of the estimated budget [default: every core]
+    #[clap(long, value_parser)]
+    threads: Option<usize>,
+    /// Percentage of the estimated time per challenge spent solving before sending a
+    /// best-effort answer [default: 80]
+    #[clap(long, value_parser)]
+    give_up_percent: Option<u32>,
+    /// Show the leaderboard, the current challenge, the solver progress and the rounds on
+    /// a live screen
+    #[clap(long, value_parser)]
+    tui: bool,
+}
+
+impl Args {
+    /// Defaults, then the config file, the environment and the options given here
+    fn client_config(self) -> Result<ClientConfig, ConfigError> {
+        let mut config = match &self.config {
+            Some(path) => ClientConfig::from_file(path)?,
+            None => ClientConfig::default(),
+        };
+        config.apply_env(|key| env::var(key).ok());
+        if let Some(name) = self.name {
+            config.name = name;
+        }
+        if let Some(addr) = self.addr {
+            config.addr = addr;
+        }
+        if let Some(name_retries) = self.name_retries {
+            config.name_retries = name_retries;
+        }
+        if self.room.is_some() {
+            config.room = self.room;
+        }
+        if self.transcript.is_some() {
+            config.transcript = self.transcript;
+        }
+        if let Some(strategy) = self.strategy {
+            config.strategy = strategy;
+        }
+        if !self.faults.is_empty() {
+            config.faults = self.faults;
+        }
+        if let Some(timeout_delay) = self.timeout_delay {
+            config.timeout_delay = timeout_delay;
+        }
+        if let Some(reconnect_attempts) = self.reconnect_attempts {
+            config.reconnect_attempts = reconnect_attempts;
+        }
+        if let Some(reconnect_delay_ms) = self.reconnect_delay_ms {
+            config.reconnect_delay_ms = reconnect_delay_ms;
+        }
+        if self.threads.is_some() {
+            config.threads = self.threads;
+        }
+        if let Some(give_up_percent) = self.give_up_percent {
+            config.give_up_percent = give_up_percent;
+        }
+        if self.tui {
+            config.tui = true;
+        }
+        Ok(config)
+    }
 }
 
 fn main() {
     let args = Args::parse();
-    let stream = std::net::TcpStream::connect(args.addr);
-    match stream {
-        Ok(mut stream) => {
-            shared::send_message(&mut stream, Message::Hello);
-            shared::read_message(&mut stream);
-            shared::send_message(
-                &mut stream,
-                Message::Subscribe(Subscribe { name: args.name }),
-            );
-            let mut public_leader_board = Vec::new();
-            loop {
-                let response = shared::read_message(&mut stream);
-                match serde_json::from_str(&response) {
-                    Ok(message) => match message {
-                        Message::SubscribeResult(res) => match res {
-                            SubscribeResult::Ok => {}
-                            SubscribeResult::Err(..) => {
-                                panic!("Please restart the client with a new name :)")
-                            }
-                        },
-                        Message::PublicLeaderBoard(leader_board) => {
-                            public_leader_board = leader_board.0
-                        }
-                        Message::Challenge(response) => {
-                            match response {
-                                MD5HashCash(md5_hash_cash_input) => {
-                                    let challenge = MD5HashCashChallenge::new(md5_hash_cash_input);
-                                    shared::send_message(
-                                        &mut stream,
-                                        Message::ChallengeResult(ChallengeResult {
-                                            answer: ChallengeAnswer::MD5HashCash(
-                                                MD5HashCashChallenge::solve(&challenge),
-                                            ),
-                                            next_target: next_target(&public_leader_board),
-                                        }),
-                                    );
-                                }
-                                MonstrousMaze(monstrous_maze_input) => {
-                                    let challenge =
-                                        MonstrousMazeChallenge::new(monstrous_maze_input);
-                                    shared::send_message(
-                                        &mut stream,
-                                        Message::ChallengeResult(ChallengeResult {
-                                            answer: ChallengeAnswer::MonstrousMaze(
-                                                MonstrousMazeChallenge::solve(&challenge),
-                                            ),
-                                            next_target: next_target(&public_leader_board),
-                                        }),
-                                    );
-                                }
-                            };
-                        }
-                        Message::EndOfGame(..) => {
-                            break;
-                        }
-                        _ => {}
-                    },
-                    Err(err) => {
-                        panic!("Failed to deserialize the message received: {}", err)
-                    }
-                }
-            }
-        }
-        Err(_err) => panic!("Cannot connect: {_err}"),
+    let print_config = args.print_config;
+    let config = match args.client_config() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+    if print_config {
+        println!("{}", config.to_json());
+        return;
+    }
+    let transcript = match config
+        .transcript
+        .as_ref()
+        .map(Transcript::create)
+        .transpose()
+    {
+        Ok(transcript) => transcript,
+        Err(err) => {
+            eprintln!("Cannot create the transcript: {}", err);
+            process::exit(1);
+        }
+    };
+    let session = match ClientSession::connect(
+        &config.addr,
+        &config.name,
+        config.name_retries,
+        config.room.as_deref(),
+        transcript,
+    ) {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+    println!("Subscribed as {}", session.name());
+    let name = session.name().to_string();
+
+    let dashboard = Arc::new(Dashboard::default());
+    if config.tui {
+        let tui_dashboard = Arc::clone(&dashboard);
+        thread::spawn(move || tui::run(&tui_dashboard));
+    }
+    let faults = Faults::new(config.faults, Duration::from_secs(config.timeout_delay));
+    if faults.reconnect() {
+        faults::reconnect(&config.addr, &name, config.room.as_deref(), &dashboard);
     }
-}
 
-fn next_target(public_leader_board: &Vec<PublicPlayer>) -> String {
-    let mut rng = rand::thread_rng();
-    public_leader_board[rng.gen_range(0..public_leader_board.len())]
-        .name
-        .to_string()
+    let backoff = Backoff::new(
+        config.reconnect_attempts,
+        Duration::from_millis(config.reconnect_delay_ms),
+    );
+    let budget = RoundBudget::new(SolverLimits::new(config.threads, config.give_up_percent));
+    let played = game::play(
+        session,
+        config.strategy.build(),
+        faults,
+        backoff,
+        budget,
+        &dashboard,
+    );
+    if config.tui {
+        // The end of the game may come faster than the refresh of the screen
+        tui::draw(&dashboard.render(Instant::now()));
+    }
+    let leader_board = match played {
+        Ok(leader_board) => leader_board,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+    match ranking::final_rank(&leader_board.0, &name) {
+        Some(rank) => {
+            println!("Final rank: {}", rank);
+            process::exit(ranking::exit_code(rank));
+        }
+        None => {
+            eprintln!("{} is missing from the final leaderboard", name);
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,123 @@
+use shared::Message;
+use std::io::Read;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Time the client has to send each expected message
+const EXPECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One step of the script played by a [`MockServer`]
+pub enum Step {
+    /// Sends a message to the client
+    Send(Message),
+    /// Waits for the next message of the client and checks it
+    Expect {
+        description: &'static str,
+        check: Box<dyn Fn(&Message) -> bool + Send>,
+    },
+    /// Closes the writing half of the connection, like the server after `EndOfGame`
+    Close,
+    /// Waits for the client to close its side of the connection
+    ExpectClosed,
+}
+
+impl Step {
+    pub fn expect(
+        description: &'static str,
+        check: impl Fn(&Message) -> bool + Send + 'static,
+    ) -> Step {
+        Step::Expect {
+            description,
+            check: Box::new(check),
+        }
+    }
+}
+
+/// Accepts one client and plays a script of [`Step`]s with it, in a background thread.
+pub struct MockServer {
+    addr: String,
+    handle: JoinHandle<Result<(), String>>,
+}
+
+impl MockServer {
+    pub fn start(script: Vec<Step>) -> MockServer {
+        MockServer::start_sessions(vec![script])
+    }
+
+    /// Plays each script with a new connection, in order, like a client that reconnects.
+    pub fn start_sessions(scripts: Vec<Vec<Step>>) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Cannot bind the mock server");
+        let addr = listener
+            .local_addr()
+            .expect("Mock server without address")
+            .to_string();
+        let handle = thread::spawn(move || {
+            for (session, script) in scripts.into_iter().enumerate() {
+                let (stream, _) = listener.accept().map_err(|err| err.to_string())?;
+                stream
+                    .set_read_timeout(Some(EXPECT_TIMEOUT))
+                    .map_err(|err| err.to_string())?;
+                play_script(&stream, script)
+                    .map_err(|err| format!("connection {}: {}", session + 1, err))?;
+            }
+            Ok(())
+        });
+        MockServer { addr, handle }
+    }
+
+    pub fn addr(&self) -> String {
+        self.addr.clone()
+    }
+
+    /// Waits for the end of the script; the error describes the first step that failed.
+    pub fn finish(self) -> Result<(), String> {
+        self.handle
+            .join()
+            .unwrap_or_else(|_| Err("Mock server panicked".to_string()))
+    }
+}
+
+fn play_script(stream: &TcpStream, script: Vec<Step>) -> Result<(), String> {
+    for (index, step) in script.into_iter().enumerate() {
+        match step {
+            Step::Send(message) => shared::write_message(stream, message)
+                .map_err(|err| format!("step {}: cannot send: {}", index, err))?,
+            Step::Expect { description, check } => {
+                let message = shared::receive_message(stream).map_err(|err| {
+                    format!("step {}: expected {}, got: {}", index, description, err)
+                })?;
+                if !check(&message) {
+                    return Err(format!(
+                        "step {}: expected {}, got {:?}",
+                        index, description, message
+                    ));
+                }
+            }
+            Step::Close => stream
+                .shutdown(Shutdown::Write)
+                .map_err(|err| format!("step {}: cannot close: {}", index, err))?,
+            Step::ExpectClosed => {
+                let mut remaining = Vec::new();
+                let mut stream = stream;
+                match stream.read_to_end(&mut remaining) {
+                    Ok(_) if remaining.is_empty() => {}
+                    Ok(_) => {
+                        return Err(format!(
+                            "step {}: expected the connection to be closed, got {} bytes",
+                            index,
+                            remaining.len()
+                        ))
+                    }
+                    Err(err) => {
+                        return Err(format!(
+                            "step {}: expected the connection to be closed, got: {}",
+                            index, err
+                        ))
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
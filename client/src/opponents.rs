@@ -0,0 +1,222 @@
+use shared::{ChallengeValue, RoundSummary};
+use std::collections::HashMap;
+
+/// Results of one player on one kind of challenge
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChallengeStats {
+    pub attempts: u32,
+    /// `BadResult` and `Timeout`
+    pub failures: u32,
+}
+
+impl ChallengeStats {
+    /// Laplace estimate: a challenge never attempted fails one time out of two
+    pub fn failure_rate(&self) -> f64 {
+        (self.failures + 1) as f64 / (self.attempts + 2) as f64
+    }
+}
+
+/// What the round summaries told about one player
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PlayerStats {
+    /// Per challenge name
+    pub challenges: HashMap<String, ChallengeStats>,
+    pub timeouts: u32,
+    /// Reported `Unreachable`: the player left the game
+    pub unreachable: bool,
+    /// Answers reported with their time, `Ok` and `BadResult`
+    pub answers: u32,
+    pub total_used_time: f64,
+    /// Times the player passed the potato to each target
+    pub targets: HashMap<String, u32>,
+}
+
+impl PlayerStats {
+    /// Mean seconds per answer, `None` before the first answer
+    pub fn mean_used_time(&self) -> Option<f64> {
+        if self.answers == 0 {
+            return None;
+        }
+        Some(self.total_used_time / self.answers as f64)
+    }
+
+    /// Share of the challenges the player did not answer in time
+    pub fn timeout_rate(&self) -> f64 {
+        let attempts: u32 = self.challenges.values().map(|stats| stats.attempts).sum();
+        if attempts == 0 {
+            return 0.0;
+        }
+        self.timeouts as f64 / attempts as f64
+    }
+
+    /// Chance to fail a challenge drawn among `challenges`, the kinds seen in the game
+    pub fn failure_probability(&self, challenges: &[String]) -> f64 {
+        if challenges.is_empty() {
+            return ChallengeStats::default().failure_rate();
+        }
+        let total: f64 = challenges
+            .iter()
+            .map(|challenge| {
+                self.challenges
+                    .get(challenge)
+                    .cloned()
+                    .unwrap_or_default()
+                    .failure_rate()
+            })
+            .sum();
+        total / challenges.len() as f64
+    }
+
+    pub fn times_targeted(&self, name: &str) -> u32 {
+        self.targets.get(name).copied().unwrap_or(0)
+    }
+}
+
+/// Statistics on every player, fed with each `RoundSummary` of the game
+#[derive(Debug, Default)]
+pub struct OpponentModel {
+    players: HashMap<String, PlayerStats>,
+    /// Kinds of challenge seen so far, in order of appearance
+    challenges: Vec<String>,
+}
+
+impl OpponentModel {
+    pub fn observe(&mut self, round_summary: &RoundSummary) {
+        if !self.challenges.contains(&round_summary.challenge) {
+            self.challenges.push(round_summary.challenge.clone());
+        }
+        for reported in round_summary.chain.iter() {
+            let stats = self.players.entry(reported.name.clone()).or_default();
+            let (failed, used_time, next_target) = match &reported.value {
+                ChallengeValue::Unreachable => {
+                    stats.unreachable = true;
+                    continue;
+                }
+                ChallengeValue::Timeout => {
+                    stats.timeouts += 1;
+                    (true, None, None)
+                }
+                ChallengeValue::BadResult {
+                    used_time,
+                    next_target,
+                } => (true, Some(*used_time), Some(next_target)),
+                ChallengeValue::Ok {
+                    used_time,
+                    next_target,
+                } => (false, Some(*used_time), Some(next_target)),
+            };
+            let challenge = stats
+                .challenges
+                .entry(round_summary.challenge.clone())
+                .or_default();
+            challenge.attempts += 1;
+            if failed {
+                challenge.failures += 1;
+            }
+            if let Some(used_time) = used_time {
+                stats.answers += 1;
+                stats.total_used_time += used_time;
+            }
+            if let Some(next_target) = next_target.filter(|target| !target.is_empty()) {
+                *stats.targets.entry(next_target.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Statistics of `name`, empty for a player never seen in a round summary
+    pub fn stats(&self, name: &str) -> PlayerStats {
+        self.players.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Chance that `name` fails the next challenge, see [`PlayerStats::failure_probability`]
+    pub fn failure_probability(&self, name: &str) -> f64 {
+        self.stats(name).failure_probability(&self.challenges)
+    }
+}
+
+#[cfg(test)]
+mod opponents_tests {
+    use crate::opponents::OpponentModel;
+    use shared::{ChallengeValue, ReportedChallengeResult, RoundSummary};
+
+    fn reported(name: &str, value: ChallengeValue) -> ReportedChallengeResult {
+        ReportedChallengeResult {
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    fn ok(used_time: f64, next_target: &str) -> ChallengeValue {
+        ChallengeValue::Ok {
+            used_time,
+            next_target: next_target.to_string(),
+        }
+    }
+
+    fn bad_result(used_time: f64, next_target: &str) -> ChallengeValue {
+        ChallengeValue::BadResult {
+            used_time,
+            next_target: next_target.to_string(),
+        }
+    }
+
+    fn summary(challenge: &str, chain: Vec<ReportedChallengeResult>) -> RoundSummary {
+        RoundSummary {
+            challenge: challenge.to_string(),
+            chain,
+        }
+    }
+
+    fn model() -> OpponentModel {
+        let mut model = OpponentModel::default();
+        model.observe(&summary(
+            "MD5HashCash",
+            vec![reported("dark_salad", ok(0.5, "free_potato"))],
+        ));
+        model.observe(&summary(
+            "MonstrousMaze",
+            vec![reported("dark_salad", bad_result(1.5, "free_potato"))],
+        ));
+        model.observe(&summary(
+            "MonstrousMaze",
+            vec![reported("cold_soup", ChallengeValue::Timeout)],
+        ));
+        model.observe(&summary(
+            "MD5HashCash",
+            vec![reported("hot_potato", ChallengeValue::Unreachable)],
+        ));
+        model
+    }
+
+    #[test]
+    fn round_summaries_should_be_counted_per_challenge() {
+        let dark_salad = model().stats("dark_salad");
+        assert_eq!(dark_salad.challenges["MD5HashCash"].attempts, 1);
+        assert_eq!(dark_salad.challenges["MD5HashCash"].failures, 0);
+        assert_eq!(dark_salad.challenges["MonstrousMaze"].failures, 1);
+        assert_eq!(dark_salad.mean_used_time(), Some(1.0));
+        assert_eq!(dark_salad.times_targeted("free_potato"), 2);
+        assert_eq!(dark_salad.timeout_rate(), 0.0);
+    }
+
+    #[test]
+    fn timeouts_and_unreachable_players_should_be_recorded() {
+        let model = model();
+        let cold_soup = model.stats("cold_soup");
+        assert_eq!(cold_soup.timeouts, 1);
+        assert_eq!(cold_soup.timeout_rate(), 1.0);
+        assert_eq!(cold_soup.mean_used_time(), None);
+        assert!(model.stats("hot_potato").unreachable);
+    }
+
+    #[test]
+    fn failure_probability_should_average_the_challenges_of_the_game() {
+        let model = model();
+        // MD5HashCash 1/3 and MonstrousMaze 2/3
+        assert!((model.failure_probability("dark_salad") - 0.5).abs() < 1e-9);
+        // MD5HashCash unknown 1/2 and MonstrousMaze 2/3
+        assert!((model.failure_probability("cold_soup") - 7.0 / 12.0).abs() < 1e-9);
+        assert_eq!(model.failure_probability("unknown"), 0.5);
+        assert_eq!(OpponentModel::default().failure_probability("unknown"), 0.5);
+    }
+}
@@ -0,0 +1,76 @@
+use shared::PublicPlayer;
+
+/// Rank of `name` in the final leaderboard, starting at 1.
+///
+/// Players are ordered by score, then by number of rounds played (`steps`);
+/// tied players share the same rank.
+pub fn final_rank(leader_board: &[PublicPlayer], name: &str) -> Option<usize> {
+    let player = leader_board.iter().find(|player| player.name == name)?;
+    let better_players = leader_board
+        .iter()
+        .filter(|other| (other.score, other.steps) > (player.score, player.steps))
+        .count();
+    Some(better_players + 1)
+}
+
+/// Added to the rank of a player who did not win, above the statuses used for errors
+/// (`1`) and bad arguments (`2`)
+const RANK_EXIT_CODE_BASE: usize = 10;
+/// Highest status, the ones above are used by shells
+const MAX_EXIT_CODE: usize = 125;
+
+/// Process exit status for a final rank: `0` for a winner, `10 + rank` otherwise.
+///
+/// The second player exits with `12`, the third with `13`…
+pub fn exit_code(rank: usize) -> i32 {
+    if rank <= 1 {
+        0
+    } else {
+        (RANK_EXIT_CODE_BASE + rank).min(MAX_EXIT_CODE) as i32
+    }
+}
+
+#[cfg(test)]
+mod ranking_tests {
+    use crate::ranking::{exit_code, final_rank};
+    use shared::PublicPlayer;
+
+    fn player(name: &str, score: i32, steps: u32) -> PublicPlayer {
+        PublicPlayer {
+            name: name.to_string(),
+            stream_id: "127.0.0.1".to_string(),
+            score,
+            steps,
+            is_active: true,
+            total_used_time: 0.0,
+        }
+    }
+
+    #[test]
+    fn rank_should_follow_score_then_steps() {
+        let leader_board = vec![
+            player("free_patato", 6, 20),
+            player("dark_salad", 10, 2),
+            player("hot_potato", 6, 30),
+        ];
+        assert_eq!(final_rank(&leader_board, "dark_salad"), Some(1));
+        assert_eq!(final_rank(&leader_board, "hot_potato"), Some(2));
+        assert_eq!(final_rank(&leader_board, "free_patato"), Some(3));
+        assert_eq!(final_rank(&leader_board, "unknown"), None);
+    }
+
+    #[test]
+    fn tied_players_should_share_their_rank() {
+        let leader_board = vec![player("free_patato", 0, 3), player("dark_salad", 0, 3)];
+        assert_eq!(final_rank(&leader_board, "free_patato"), Some(1));
+        assert_eq!(final_rank(&leader_board, "dark_salad"), Some(1));
+    }
+
+    #[test]
+    fn winner_should_exit_successfully() {
+        assert_eq!(exit_code(1), 0);
+        assert_eq!(exit_code(2), 12);
+        assert_eq!(exit_code(3), 13);
+        assert_eq!(exit_code(1000), 125);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::session::{ClientSession, SessionError};
+use std::thread;
+use std::time::Duration;
+
+/// How a session lost during the game is resumed: up to `attempts` new connections,
+/// the first one after `first_delay` and each next one after twice the previous delay
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Backoff {
+    pub attempts: u32,
+    pub first_delay: Duration,
+}
+
+impl Backoff {
+    pub fn new(attempts: u32, first_delay: Duration) -> Backoff {
+        Backoff {
+            attempts,
+            first_delay,
+        }
+    }
+
+    /// Connects `session` again after it was `lost`, and takes its slot back in the game.
+    ///
+    /// A server that refuses the name does not let the player come back, so it is not
+    /// asked again. The last error is returned when every attempt failed.
+    pub fn resume(
+        &self,
+        session: &mut ClientSession,
+        lost: SessionError,
+    ) -> Result<(), SessionError> {
+        println!("{}", lost);
+        let mut error = lost;
+        let mut delay = self.first_delay;
+        for attempt in 1..=self.attempts {
+            thread::sleep(delay);
+            println!(
+                "Reconnecting as {} ({}/{})",
+                session.name(),
+                attempt,
+                self.attempts
+            );
+            match session.reconnect() {
+                Ok(()) => {
+                    println!("Back in the game");
+                    return Ok(());
+                }
+                Err(
+                    err @ (SessionError::AlreadyRegistered(..) | SessionError::InvalidName(..)),
+                ) => return Err(err),
+                Err(err) => {
+                    println!("Reconnection failed: {}", err);
+                    error = err;
+                }
+            }
+            delay *= 2;
+        }
+        Err(error)
+    }
+}
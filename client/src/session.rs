@@ -0,0 +1,285 @@
+use shared::transcript::{Direction, Transcript};
+use shared::{JoinRoom, Message, Subscribe, SubscribeError, SubscribeResult};
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::time::Duration;
+
+/// Time the server has to close its side of the connection after `EndOfGame`
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A connection to the server that went through `Hello` / `Welcome` / `Subscribe`.
+pub struct ClientSession {
+    stream: TcpStream,
+    /// Server address and room, to connect again after a lost connection
+    addr: String,
+    room: Option<String>,
+    name: String,
+    /// Where every message sent and received is recorded
+    transcript: Option<Transcript>,
+    /// Round in progress as recorded in the transcript, counted like the server does
+    round: u32,
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    /// The TCP stream could not be opened, written or read
+    Io(std::io::Error),
+    /// The server answered with a message that does not fit the handshake
+    UnexpectedMessage(Message),
+    /// The server refused the name as invalid
+    InvalidName(String),
+    /// The name (and every retried suffix) is already taken
+    AlreadyRegistered(String),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Io(err) => write!(f, "Connection error: {}", err),
+            SessionError::UnexpectedMessage(message) => {
+                write!(f, "Unexpected message from the server: {:?}", message)
+            }
+            SessionError::InvalidName(name) => write!(f, "Invalid name: {}", name),
+            SessionError::AlreadyRegistered(name) => {
+                write!(f, "Name already registered: {}", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<std::io::Error> for SessionError {
+    fn from(err: std::io::Error) -> Self {
+        SessionError::Io(err)
+    }
+}
+
+impl ClientSession {
+    /// Connects to `addr` and subscribes as `name`, in `room` if given or else in the
+    /// server default room.
+    ///
+    /// When the name is `AlreadyRegistered`, up to `name_retries` suffixed names
+    /// (`name-2`, `name-3`, …) are tried on the same connection.
+    pub fn connect(
+        addr: &str,
+        name: &str,
+        name_retries: u32,
+        room: Option<&str>,
+        transcript: Option<Transcript>,
+    ) -> Result<ClientSession, SessionError> {
+        let stream = TcpStream::connect(addr)?;
+        let mut session = ClientSession {
+            stream,
+            addr: addr.to_string(),
+            room: room.map(str::to_string),
+            name: name.to_string(),
+            transcript,
+            round: 0,
+        };
+        session.hello()?;
+        session.join_room()?;
+
+        for attempt in 0..=name_retries {
+            let candidate = suffixed_name(name, attempt);
+            match session.subscribe(&candidate)? {
+                SubscribeResult::Ok => {
+                    session.name = candidate;
+                    return Ok(session);
+                }
+                SubscribeResult::Err(SubscribeError::InvalidName) => {
+                    return Err(SessionError::InvalidName(candidate));
+                }
+                SubscribeResult::Err(SubscribeError::AlreadyRegistered) => {
+                    println!("Name {} is already registered", candidate);
+                }
+            }
+        }
+        Err(SessionError::AlreadyRegistered(suffixed_name(
+            name,
+            name_retries,
+        )))
+    }
+
+    /// Opens a new connection to the same server and room, and subscribes again under
+    /// the name accepted for this session, to take its slot back in the game.
+    pub fn reconnect(&mut self) -> Result<(), SessionError> {
+        self.stream = TcpStream::connect(&self.addr)?;
+        self.hello()?;
+        self.join_room()?;
+        let name = self.name.clone();
+        match self.subscribe(&name)? {
+            SubscribeResult::Ok => Ok(()),
+            SubscribeResult::Err(SubscribeError::InvalidName) => {
+                Err(SessionError::InvalidName(name))
+            }
+            SubscribeResult::Err(SubscribeError::AlreadyRegistered) => {
+                Err(SessionError::AlreadyRegistered(name))
+            }
+        }
+    }
+
+    /// Name the server accepted for this session
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn send(&self, message: Message) -> Result<(), SessionError> {
+        shared::write_recorded_message(
+            &self.stream,
+            message,
+            self.transcript.as_ref(),
+            self.round,
+        )?;
+        Ok(())
+    }
+
+    /// Writes `bytes` as is, without framing nor recording: only to inject broken frames
+    pub fn send_raw(&self, bytes: &[u8]) -> Result<(), SessionError> {
+        (&self.stream).write_all(bytes)?;
+        Ok(())
+    }
+
+    /// Stops writing, leaving the server with whatever was sent so far
+    pub fn shutdown_write(&self) -> Result<(), SessionError> {
+        self.stream.shutdown(Shutdown::Write)?;
+        Ok(())
+    }
+
+    /// Drops the connection in both directions, as if the network failed
+    pub fn disconnect(&self) -> Result<(), SessionError> {
+        self.stream.shutdown(Shutdown::Both)?;
+        Ok(())
+    }
+
+    pub fn receive(&mut self) -> Result<Message, SessionError> {
+        let message = shared::receive_message(&self.stream)?;
+        // The game starts with a first leader board, each round ends with a summary
+        if matches!(message, Message::PublicLeaderBoard(..)) && self.round == 0 {
+            self.round = 1;
+        }
+        if let Some(transcript) = &self.transcript {
+            shared::record_message(
+                transcript,
+                Direction::Received,
+                &self.stream,
+                self.round,
+                &message,
+            );
+        }
+        if matches!(message, Message::RoundSummary(..)) {
+            self.round += 1;
+        }
+        Ok(message)
+    }
+
+    /// Closes the connection after `EndOfGame`: stops writing, then waits (briefly) for
+    /// the server to close its side.
+    pub fn close(self) -> Result<(), SessionError> {
+        self.stream.shutdown(Shutdown::Write)?;
+        self.stream.set_read_timeout(Some(CLOSE_TIMEOUT))?;
+        let mut remaining = Vec::new();
+        match (&self.stream).read_to_end(&mut remaining) {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::ConnectionReset => Ok(()),
+            Err(err) => Err(SessionError::Io(err)),
+        }
+    }
+
+    fn hello(&mut self) -> Result<(), SessionError> {
+        self.send(Message::Hello)?;
+        match self.receive()? {
+            Message::Welcome(..) => Ok(()),
+            message => Err(SessionError::UnexpectedMessage(message)),
+        }
+    }
+
+    fn join_room(&mut self) -> Result<(), SessionError> {
+        match self.room.clone() {
+            Some(room) => self.send(Message::JoinRoom(JoinRoom { room })),
+            None => Ok(()),
+        }
+    }
+
+    fn subscribe(&mut self, name: &str) -> Result<SubscribeResult, SessionError> {
+        self.send(Message::Subscribe(Subscribe {
+            name: name.to_string(),
+        }))?;
+        match self.receive()? {
+            Message::SubscribeResult(result) => Ok(result),
+            message => Err(SessionError::UnexpectedMessage(message)),
+        }
+    }
+}
+
+/// Name used for the given retry: `name` first, then `name-2`, `name-3`, …
+pub fn suffixed_name(name: &str, attempt: u32) -> String {
+    if attempt == 0 {
+        name.to_string()
+    } else {
+        format!("{}-{}", name, attempt + 1)
+    }
+}
+
+#[cfg(test)]
+mod session_tests {
+    use crate::mock_server::{MockServer, Step};
+    use crate::session::{suffixed_name, ClientSession, SessionError};
+    use shared::{Message, SubscribeError, SubscribeResult, Welcome};
+
+    fn expect_subscribe(name: &'static str) -> Step {
+        Step::expect(name, move |message| match message {
+            Message::Subscribe(subscribe) => subscribe.name == name,
+            _ => false,
+        })
+    }
+
+    #[test]
+    fn first_attempt_should_keep_the_name() {
+        assert_eq!(suffixed_name("free_potato", 0), "free_potato");
+    }
+
+    #[test]
+    fn retries_should_be_suffixed_from_two() {
+        assert_eq!(suffixed_name("free_potato", 1), "free_potato-2");
+        assert_eq!(suffixed_name("free_potato", 4), "free_potato-5");
+    }
+
+    #[test]
+    fn taken_name_should_be_retried_with_a_suffix() {
+        let server = MockServer::start(vec![
+            Step::expect("Hello", |message| matches!(message, Message::Hello)),
+            Step::Send(Message::Welcome(Welcome { version: 1 })),
+            expect_subscribe("free_potato"),
+            Step::Send(Message::SubscribeResult(SubscribeResult::Err(
+                SubscribeError::AlreadyRegistered,
+            ))),
+            expect_subscribe("free_potato-2"),
+            Step::Send(Message::SubscribeResult(SubscribeResult::Ok)),
+        ]);
+        let session = ClientSession::connect(&server.addr(), "free_potato", 1, None, None).unwrap();
+        server.finish().unwrap();
+        assert_eq!(session.name(), "free_potato-2");
+    }
+
+    #[test]
+    fn room_should_be_joined_before_subscribing() {
+        let server = MockServer::start(vec![
+            Step::expect("Hello", |message| matches!(message, Message::Hello)),
+            Step::Send(Message::Welcome(Welcome { version: 1 })),
+            Step::expect("JoinRoom tournament", |message| match message {
+                Message::JoinRoom(join_room) => join_room.room == "tournament",
+                _ => false,
+            }),
+            expect_subscribe("free_potato"),
+            Step::Send(Message::SubscribeResult(SubscribeResult::Err(
+                SubscribeError::InvalidName,
+            ))),
+        ]);
+        let result =
+            ClientSession::connect(&server.addr(), "free_potato", 3, Some("tournament"), None);
+        server.finish().unwrap();
+        assert!(matches!(result, Err(SessionError::InvalidName(name)) if name == "free_potato"));
+    }
+}
@@ -0,0 +1,352 @@
+use crate::opponents::OpponentModel;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use shared::PublicPlayer;
+use std::cmp::Ordering;
+
+/// Chooses who receives the potato after each solved challenge
+pub trait TargetStrategy {
+    /// Name of the next target among the `leader_board`, where `me` is our own name and
+    /// `opponents` what the round summaries told about every player.
+    ///
+    /// An empty name lets the server choose a random player.
+    fn next_target(
+        &mut self,
+        leader_board: &[PublicPlayer],
+        me: &str,
+        opponents: &OpponentModel,
+    ) -> String;
+}
+
+/// Target strategies selectable with `--strategy`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// Any player of the leader board, ourselves and inactive players included
+    Random,
+    /// Any other player
+    NeverSelf,
+    /// Any active player
+    AvoidInactive,
+    /// The active opponent with the highest score
+    Strongest,
+    /// The active opponent with the highest mean time per challenge
+    Slowest,
+    /// Every active opponent in turn, in alphabetical order
+    RoundRobin,
+    /// The active opponent most likely to fail the next challenge, from the round summaries
+    LikelyToFail,
+}
+
+impl Strategy {
+    pub fn build(self) -> Box<dyn TargetStrategy> {
+        match self {
+            Strategy::Random => Box::new(Random),
+            Strategy::NeverSelf => Box::new(NeverSelf),
+            Strategy::AvoidInactive => Box::new(AvoidInactive),
+            Strategy::Strongest => Box::new(Strongest),
+            Strategy::Slowest => Box::new(Slowest),
+            Strategy::RoundRobin => Box::new(RoundRobin::default()),
+            Strategy::LikelyToFail => Box::new(LikelyToFail),
+        }
+    }
+}
+
+/// Picks any name of the leader board
+pub struct Random;
+
+impl TargetStrategy for Random {
+    fn next_target(
+        &mut self,
+        leader_board: &[PublicPlayer],
+        _me: &str,
+        _opponents: &OpponentModel,
+    ) -> String {
+        random_name(leader_board.iter())
+    }
+}
+
+/// Picks any name but ours
+pub struct NeverSelf;
+
+impl TargetStrategy for NeverSelf {
+    fn next_target(
+        &mut self,
+        leader_board: &[PublicPlayer],
+        me: &str,
+        _opponents: &OpponentModel,
+    ) -> String {
+        random_name(leader_board.iter().filter(|player| player.name != me))
+    }
+}
+
+/// Picks any active player, ourselves included
+pub struct AvoidInactive;
+
+impl TargetStrategy for AvoidInactive {
+    fn next_target(
+        &mut self,
+        leader_board: &[PublicPlayer],
+        _me: &str,
+        _opponents: &OpponentModel,
+    ) -> String {
+        random_name(leader_board.iter().filter(|player| player.is_active))
+    }
+}
+
+/// Picks the opponent with the highest score, an empty name when alone
+pub struct Strongest;
+
+impl TargetStrategy for Strongest {
+    fn next_target(
+        &mut self,
+        leader_board: &[PublicPlayer],
+        me: &str,
+        _opponents: &OpponentModel,
+    ) -> String {
+        opponents_of(leader_board, me)
+            .max_by_key(|player| player.score)
+            .map_or_else(String::new, |player| player.name.clone())
+    }
+}
+
+/// Picks the opponent with the highest mean `used_time`, an empty name when alone
+pub struct Slowest;
+
+impl TargetStrategy for Slowest {
+    fn next_target(
+        &mut self,
+        leader_board: &[PublicPlayer],
+        me: &str,
+        _opponents: &OpponentModel,
+    ) -> String {
+        opponents_of(leader_board, me)
+            .max_by(|a, b| mean_used_time(a).total_cmp(&mean_used_time(b)))
+            .map_or_else(String::new, |player| player.name.clone())
+    }
+}
+
+/// Picks the opponent after its last target in alphabetical order
+#[derive(Default)]
+pub struct RoundRobin {
+    last_target: Option<String>,
+}
+
+impl TargetStrategy for RoundRobin {
+    fn next_target(
+        &mut self,
+        leader_board: &[PublicPlayer],
+        me: &str,
+        _opponents: &OpponentModel,
+    ) -> String {
+        let mut names: Vec<&str> = opponents_of(leader_board, me)
+            .map(|player| player.name.as_str())
+            .collect();
+        names.sort_unstable();
+        let next = match &self.last_target {
+            Some(last) => names
+                .iter()
+                .find(|name| **name > last.as_str())
+                .or_else(|| names.first()),
+            None => names.first(),
+        };
+        let next = next.map_or_else(String::new, |name| name.to_string());
+        self.last_target = Some(next.clone()).filter(|name| !name.is_empty());
+        next
+    }
+}
+
+/// Picks the reachable opponent with the highest failure odds in the [`OpponentModel`]
+pub struct LikelyToFail;
+
+impl TargetStrategy for LikelyToFail {
+    fn next_target(
+        &mut self,
+        leader_board: &[PublicPlayer],
+        me: &str,
+        opponents: &OpponentModel,
+    ) -> String {
+        // Then the most timed out, the slowest, and the one least likely to send it back
+        let odds = |player: &PublicPlayer| {
+            let stats = opponents.stats(&player.name);
+            (
+                opponents.failure_probability(&player.name),
+                stats.timeout_rate(),
+                stats.mean_used_time().unwrap_or(0.0),
+                -(stats.times_targeted(me) as f64),
+            )
+        };
+        opponents_of(leader_board, me)
+            .filter(|player| !opponents.stats(&player.name).unreachable)
+            .max_by(|a, b| compare_odds(odds(a), odds(b)))
+            .map_or_else(String::new, |player| player.name.clone())
+    }
+}
+
+fn compare_odds(a: (f64, f64, f64, f64), b: (f64, f64, f64, f64)) -> Ordering {
+    a.0.total_cmp(&b.0)
+        .then(a.1.total_cmp(&b.1))
+        .then(a.2.total_cmp(&b.2))
+        .then(a.3.total_cmp(&b.3))
+}
+
+/// Active players other than `me`
+fn opponents_of<'a>(
+    leader_board: &'a [PublicPlayer],
+    me: &'a str,
+) -> impl Iterator<Item = &'a PublicPlayer> {
+    leader_board
+        .iter()
+        .filter(move |player| player.is_active && player.name != me)
+}
+
+/// Seconds per challenge, `0` for a player who has not played yet
+fn mean_used_time(player: &PublicPlayer) -> f64 {
+    if player.steps == 0 {
+        return 0.0;
+    }
+    player.total_used_time / player.steps as f64
+}
+
+/// Random player among `players`, or an empty name (any player) when there is none
+fn random_name<'a>(players: impl Iterator<Item = &'a PublicPlayer>) -> String {
+    let players: Vec<&PublicPlayer> = players.collect();
+    if players.is_empty() {
+        return String::new();
+    }
+    let mut rng = rand::thread_rng();
+    players[rng.gen_range(0..players.len())].name.to_string()
+}
+
+#[cfg(test)]
+mod strategy_tests {
+    use crate::opponents::OpponentModel;
+    use crate::strategy::Strategy;
+    use shared::{ChallengeValue, PublicPlayer, ReportedChallengeResult, RoundSummary};
+
+    fn player(name: &str, score: i32, steps: u32, total_used_time: f64) -> PublicPlayer {
+        PublicPlayer {
+            name: name.to_string(),
+            stream_id: "127.0.0.1".to_string(),
+            score,
+            steps,
+            is_active: true,
+            total_used_time,
+        }
+    }
+
+    /// free_potato is us; hot_potato left the game
+    fn leader_board() -> Vec<PublicPlayer> {
+        let mut hot_potato = player("hot_potato", 0, 1, 9.0);
+        hot_potato.is_active = false;
+        vec![
+            player("free_potato", 0, 4, 0.4),
+            player("dark_salad", -1, 4, 2.0),
+            player("cold_soup", -3, 2, 0.2),
+            hot_potato,
+        ]
+    }
+
+    fn targets(strategy: Strategy, count: usize) -> Vec<String> {
+        let mut strategy = strategy.build();
+        (0..count)
+            .map(|_| {
+                strategy.next_target(&leader_board(), "free_potato", &OpponentModel::default())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn random_should_pick_any_player() {
+        let names: Vec<String> = leader_board().into_iter().map(|p| p.name).collect();
+        assert!(targets(Strategy::Random, 50)
+            .iter()
+            .all(|target| names.contains(target)));
+    }
+
+    #[test]
+    fn never_self_should_not_pick_us() {
+        assert!(targets(Strategy::NeverSelf, 50)
+            .iter()
+            .all(|target| target != "free_potato"));
+    }
+
+    #[test]
+    fn avoid_inactive_should_not_pick_inactive_players() {
+        assert!(targets(Strategy::AvoidInactive, 50)
+            .iter()
+            .all(|target| target != "hot_potato"));
+    }
+
+    #[test]
+    fn strongest_should_pick_the_best_active_opponent() {
+        assert_eq!(targets(Strategy::Strongest, 1), ["dark_salad"]);
+    }
+
+    #[test]
+    fn slowest_should_pick_the_highest_mean_used_time() {
+        // hot_potato is slower but inactive
+        assert_eq!(targets(Strategy::Slowest, 1), ["dark_salad"]);
+    }
+
+    #[test]
+    fn round_robin_should_cycle_through_active_opponents() {
+        assert_eq!(
+            targets(Strategy::RoundRobin, 3),
+            ["cold_soup", "dark_salad", "cold_soup"]
+        );
+    }
+
+    #[test]
+    fn no_opponent_should_let_the_server_choose() {
+        let alone = vec![player("free_potato", 0, 0, 0.0)];
+        let opponents = OpponentModel::default();
+        for strategy in [
+            Strategy::Strongest,
+            Strategy::Slowest,
+            Strategy::RoundRobin,
+            Strategy::LikelyToFail,
+        ] {
+            assert_eq!(
+                strategy
+                    .build()
+                    .next_target(&alone, "free_potato", &opponents),
+                ""
+            );
+        }
+        assert_eq!(
+            Strategy::Random
+                .build()
+                .next_target(&[], "free_potato", &opponents),
+            ""
+        );
+    }
+
+    #[test]
+    fn likely_to_fail_should_pick_the_opponent_who_failed_most() {
+        let mut opponents = OpponentModel::default();
+        for (name, value) in [
+            ("dark_salad", ChallengeValue::Timeout),
+            (
+                "cold_soup",
+                ChallengeValue::Ok {
+                    used_time: 0.1,
+                    next_target: "free_potato".to_string(),
+                },
+            ),
+        ] {
+            opponents.observe(&RoundSummary {
+                challenge: "MD5HashCash".to_string(),
+                chain: vec![ReportedChallengeResult {
+                    name: name.to_string(),
+                    value,
+                }],
+            });
+        }
+        let mut strategy = Strategy::LikelyToFail.build();
+        assert_eq!(
+            strategy.next_target(&leader_board(), "free_potato", &opponents),
+            "dark_salad"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use shared::{Challenge, PublicLeaderBoard, PublicPlayer, RoundSummary};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time between two redraws of the screen
+pub const REFRESH_PERIOD: Duration = Duration::from_millis(250);
+/// Round summaries shown at the bottom of the screen, the latest ones
+const ROUND_LOG_LENGTH: usize = 10;
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// What the client knows about its game, updated by [`crate::game::play`] and drawn by
+/// [`run`] with `--tui`
+#[derive(Debug, Default)]
+pub struct Dashboard {
+    state: Mutex<DashboardState>,
+    /// Seeds hashed or maze states explored on the current challenge
+    pub tried: Arc<AtomicU64>,
+}
+
+#[derive(Debug, Default)]
+struct DashboardState {
+    connection: String,
+    leader_board: Vec<PublicPlayer>,
+    challenge: Option<Challenge>,
+    received_at: Option<Instant>,
+    /// Time spent on the current challenge, once answered
+    solved_in: Option<Duration>,
+    round_summaries: Vec<RoundSummary>,
+}
+
+impl Dashboard {
+    fn state(&self) -> MutexGuard<'_, DashboardState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Shows what the connection to the server is doing
+    pub fn connection(&self, connection: String) {
+        self.state().connection = connection;
+    }
+
+    pub fn leader_board(&self, leader_board: &[PublicPlayer]) {
+        self.state().leader_board = leader_board.to_vec();
+    }
+
+    /// A new challenge is being solved: the progress starts over
+    pub fn challenge(&self, challenge: &Challenge, received_at: Instant) {
+        let mut state = self.state();
+        state.challenge = Some(challenge.clone());
+        state.received_at = Some(received_at);
+        state.solved_in = None;
+        self.tried.store(0, Ordering::Relaxed);
+    }
+
+    pub fn solved(&self, at: Instant) {
+        let mut state = self.state();
+        state.solved_in = state
+            .received_at
+            .map(|received_at| at.saturating_duration_since(received_at));
+    }
+
+    pub fn round_summary(&self, round_summary: &RoundSummary) {
+        self.state().round_summaries.push(round_summary.clone());
+    }
+
+    /// Connection, leaderboard, current challenge with the solver progress and latest
+    /// round summaries, with ANSI styles
+    pub fn render(&self, now: Instant) -> String {
+        let state = self.state();
+        let mut screen = String::new();
+        let _ = writeln!(
+            screen,
+            "{}La patate chaude{} - {}",
+            BOLD, RESET, state.connection
+        );
+
+        title(&mut screen, "Leaderboard");
+        for line in PublicLeaderBoard(state.leader_board.clone())
+            .to_string()
+            .lines()
+        {
+            let _ = writeln!(screen, "  {}", line);
+        }
+
+        title(&mut screen, "Challenge");
+        match &state.challenge {
+            Some(Challenge::MD5HashCash(input)) => {
+                let _ = writeln!(
+                    screen,
+                    "  MD5HashCash, complexity {}, message {:?}",
+                    input.complexity, input.message
+                );
+            }
+            Some(Challenge::MonstrousMaze(input)) => {
+                let _ = writeln!(screen, "  MonstrousMaze, endurance {}", input.endurance);
+                for line in input.grid.lines() {
+                    let _ = writeln!(screen, "  {}", line);
+                }
+            }
+            None => {
+                let _ = writeln!(screen, "  None yet");
+            }
+        }
+        if let (Some(challenge), Some(received_at)) = (&state.challenge, state.received_at) {
+            let unit = match challenge {
+                Challenge::MD5HashCash(_) => "seeds",
+                Challenge::MonstrousMaze(_) => "states",
+            };
+            let (verb, elapsed) = match state.solved_in {
+                Some(solved_in) => ("Solved", solved_in),
+                None => ("Solving", now.saturating_duration_since(received_at)),
+            };
+            let tried = self.tried.load(Ordering::Relaxed);
+            let _ = writeln!(
+                screen,
+                "  {} for {:.2}s, {} {} tried ({:.0} {}/s)",
+                verb,
+                elapsed.as_secs_f64(),
+                tried,
+                unit,
+                tried as f64 / elapsed.as_secs_f64().max(0.001),
+                unit
+            );
+        }
+
+        title(&mut screen, "Rounds");
+        let skipped = state.round_summaries.len().saturating_sub(ROUND_LOG_LENGTH);
+        for (index, round_summary) in state.round_summaries.iter().enumerate().skip(skipped) {
+            let _ = writeln!(screen, "  {:>3} {}", index + 1, round_summary);
+        }
+        screen
+    }
+}
+
+fn title(screen: &mut String, title: &str) {
+    let _ = writeln!(screen, "\n{}{}{}", BOLD, title, RESET);
+}
+
+/// Replaces the terminal content with `screen`
+pub fn draw(screen: &str) {
+    print!("{}{}", CLEAR_SCREEN, screen);
+    let _ = std::io::stdout().flush();
+}
+
+/// Redraws the dashboard whenever it changes, forever.
+pub fn run(dashboard: &Dashboard) {
+    let mut last_screen = String::new();
+    loop {
+        let screen = dashboard.render(Instant::now());
+        if screen != last_screen {
+            draw(&screen);
+            last_screen = screen;
+        }
+        thread::sleep(REFRESH_PERIOD);
+    }
+}
+
+#[cfg(test)]
+mod tui_tests {
+    use crate::tui::Dashboard;
+    use shared::{Challenge, MD5HashCashInput, MonstrousMazeInput};
+    use std::sync::atomic::Ordering;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn solver_progress_should_be_shown_per_second() {
+        let dashboard = Dashboard::default();
+        let received_at = Instant::now();
+        dashboard.challenge(
+            &Challenge::MD5HashCash(MD5HashCashInput {
+                complexity: 9,
+                message: "hello".to_string(),
+            }),
+            received_at,
+        );
+        dashboard.tried.store(2048, Ordering::Relaxed);
+        let screen = dashboard.render(received_at + Duration::from_secs(2));
+        assert!(screen.contains("MD5HashCash, complexity 9, message \"hello\""));
+        assert!(screen.contains("Solving for 2.00s, 2048 seeds tried (1024 seeds/s)"));
+
+        dashboard.solved(received_at + Duration::from_secs(1));
+        let screen = dashboard.render(received_at + Duration::from_secs(2));
+        assert!(screen.contains("Solved for 1.00s, 2048 seeds tried (2048 seeds/s)"));
+    }
+
+    #[test]
+    fn new_challenge_should_restart_the_progress() {
+        let dashboard = Dashboard::default();
+        dashboard.tried.store(100, Ordering::Relaxed);
+        let received_at = Instant::now();
+        dashboard.challenge(
+            &Challenge::MonstrousMaze(MonstrousMazeInput {
+                endurance: 2,
+                grid: "|I M X|".to_string(),
+            }),
+            received_at,
+        );
+        let screen = dashboard.render(received_at);
+        assert!(screen.contains("MonstrousMaze, endurance 2\n  |I M X|"));
+        assert!(screen.contains("0 states tried"));
+    }
+}
@@ -0,0 +1,119 @@
+mod replay;
+
+use clap::{Parser, Subcommand};
+use replay::Replay;
+use shared::challenges::maze_render;
+use shared::challenges::monstrous_maze::Grid;
+use shared::transcript::TranscriptEntry;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Exit status when the transcript diverges from the replay (`1` is left for errors)
+const DIVERGENCE_EXIT_CODE: i32 = 2;
+
+#[derive(Parser, Debug)]
+#[clap(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Args {
+    /// Transcript recorded with `--transcript` by the server or a client
+    #[clap(value_parser, required = true)]
+    transcript: Option<PathBuf>,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Draw the MonstrousMaze answers of a transcript, with where each rejected one failed
+    Maze {
+        /// Transcript recorded with `--transcript` by the server or a client
+        #[clap(value_parser)]
+        transcript: PathBuf,
+        /// Also draw the answers that were accepted
+        #[clap(long, value_parser)]
+        all: bool,
+        /// Highlight the path, the monsters fought and the failure with ANSI colours
+        #[clap(long, value_parser)]
+        color: bool,
+    },
+}
+
+fn main() {
+    let args = Args::parse();
+    match (args.command, args.transcript) {
+        (
+            Some(Command::Maze {
+                transcript,
+                all,
+                color,
+            }),
+            _,
+        ) => draw_mazes(&read_transcript(&transcript), all, color),
+        (None, Some(transcript)) => check(&read_transcript(&transcript)),
+        (None, None) => unreachable!("clap requires a transcript without a subcommand"),
+    }
+}
+
+fn read_transcript(path: &Path) -> Vec<TranscriptEntry> {
+    match shared::transcript::read_transcript(path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            eprintln!("Cannot read {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Prints the recomputed leader boards and the divergences, if any
+fn check(entries: &[TranscriptEntry]) {
+    let replay = Replay::run(entries);
+
+    for game in replay.games() {
+        println!(
+            "Room {}{}",
+            game.room,
+            if game.finished { "" } else { " (not finished)" }
+        );
+        print!("{}", game.leader_board);
+    }
+    if replay.divergences().is_empty() {
+        println!("No divergence in {} messages", entries.len());
+        return;
+    }
+    for divergence in replay.divergences() {
+        println!("{}", divergence);
+    }
+    process::exit(DIVERGENCE_EXIT_CODE);
+}
+
+/// Prints the rejected MonstrousMaze answers, or all of them
+fn draw_mazes(entries: &[TranscriptEntry], all: bool, color: bool) {
+    let replay = Replay::run(entries);
+    let mut drawn = 0;
+    for maze_answer in replay.maze_answers() {
+        if maze_answer.valid && !all {
+            continue;
+        }
+        println!(
+            "[{}] round {}: {}",
+            maze_answer.room, maze_answer.round, maze_answer.name
+        );
+        let grid = Grid::new(maze_answer.input.clone());
+        println!(
+            "{}",
+            maze_render::render_path(&grid, &maze_answer.output, color)
+        );
+        drawn += 1;
+    }
+    println!(
+        "{} of {} MonstrousMaze answers drawn",
+        drawn,
+        replay.maze_answers().len()
+    );
+}
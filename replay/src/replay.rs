@@ -0,0 +1,749 @@
+use shared::challenges;
+use shared::transcript::{Direction, TranscriptEntry};
+use shared::{
+    Challenge, ChallengeAnswer, ChallengeValue, Message, MonstrousMazeInput, MonstrousMazeOutput,
+    PublicLeaderBoard, PublicPlayer, RoundSummary, SubscribeResult,
+};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+/// Room of the connections that never sent `JoinRoom`
+const DEFAULT_ROOM: &str = "default";
+/// Tolerance on `total_used_time`, which is a sum of floats
+const USED_TIME_TOLERANCE: f64 = 1e-6;
+
+/// Something the server broadcast that the replay does not agree with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub room: String,
+    pub round: u32,
+    pub description: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] round {}: {}",
+            self.room, self.round, self.description
+        )
+    }
+}
+
+/// Leader board recomputed for one game of a room
+#[derive(Debug, Clone)]
+pub struct ReplayedGame {
+    pub room: String,
+    pub leader_board: PublicLeaderBoard,
+    /// `EndOfGame` was seen for this game
+    pub finished: bool,
+}
+
+/// A MonstrousMaze answer of the transcript, with the maze it answers
+#[derive(Debug, Clone)]
+pub struct MazeAnswer {
+    pub room: String,
+    pub round: u32,
+    pub name: String,
+    pub input: MonstrousMazeInput,
+    pub output: MonstrousMazeOutput,
+    pub valid: bool,
+}
+
+/// One game being replayed
+#[derive(Default)]
+struct GameReplay {
+    players: Vec<PublicPlayer>,
+    /// Name of each subscribed connection, by `stream_id`
+    names: HashMap<String, String>,
+    /// Names sent in `Subscribe`, waiting for `SubscribeResult`
+    pending_names: HashMap<String, String>,
+    /// Last challenge sent to each connection, waiting for its `ChallengeResult`
+    challenges: HashMap<String, Challenge>,
+    /// Answers checked with `verify`, waiting for the `RoundSummary` that reports them
+    verified_answers: VecDeque<(String, bool)>,
+    /// At least one answer was scored
+    started: bool,
+    finished: bool,
+    /// A broadcast is recorded once per recipient: only its first copy is checked
+    last_broadcast: Option<String>,
+}
+
+/// Replays a transcript recorded by the server or by a client.
+///
+/// Every answer is checked again with `shared::challenges` and the scores are recomputed
+/// from the rules (one step per answer, one point lost per bad answer); each
+/// `RoundSummary`, `PublicLeaderBoard` and `EndOfGame` is then compared to the replay.
+pub struct Replay {
+    /// Direction of the messages sent by the server: `Sent` in a server transcript,
+    /// `Received` in a client one
+    from_server: Direction,
+    room_of_connections: HashMap<String, String>,
+    games: Vec<(String, GameReplay)>,
+    divergences: Vec<Divergence>,
+    maze_answers: Vec<MazeAnswer>,
+}
+
+impl Replay {
+    pub fn run(entries: &[TranscriptEntry]) -> Replay {
+        let recorded_by_client = entries
+            .iter()
+            .find(|entry| matches!(entry.message, Message::Hello))
+            .is_some_and(|entry| entry.direction == Direction::Sent);
+        let mut replay = Replay {
+            from_server: if recorded_by_client {
+                Direction::Received
+            } else {
+                Direction::Sent
+            },
+            room_of_connections: HashMap::new(),
+            games: Vec::new(),
+            divergences: Vec::new(),
+            maze_answers: Vec::new(),
+        };
+        for entry in entries {
+            replay.replay_entry(entry);
+        }
+        replay
+    }
+
+    pub fn divergences(&self) -> &[Divergence] {
+        &self.divergences
+    }
+
+    /// Every answer to a MonstrousMaze challenge, in the order of the transcript
+    pub fn maze_answers(&self) -> &[MazeAnswer] {
+        &self.maze_answers
+    }
+
+    /// Recomputed leader boards, in the order the games were started
+    pub fn games(&self) -> Vec<ReplayedGame> {
+        self.games
+            .iter()
+            .map(|(room, game)| ReplayedGame {
+                room: room.clone(),
+                leader_board: PublicLeaderBoard(game.players.clone()),
+                finished: game.finished,
+            })
+            .collect()
+    }
+
+    fn replay_entry(&mut self, entry: &TranscriptEntry) {
+        let from_server = entry.direction == self.from_server;
+        let stream_id = entry.stream_id.as_str();
+        let room = self
+            .room_of_connections
+            .get(stream_id)
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_ROOM.to_string());
+        let mut divergences = Vec::new();
+        match (&entry.message, from_server) {
+            (Message::JoinRoom(join_room), false) => {
+                self.room_of_connections
+                    .insert(stream_id.to_string(), join_room.room.clone());
+            }
+            (Message::Subscribe(subscribe), false) => {
+                self.next_game(&room)
+                    .pending_names
+                    .insert(stream_id.to_string(), subscribe.name.clone());
+            }
+            (Message::SubscribeResult(SubscribeResult::Ok), true) => {
+                let game = self.game(&room);
+                if let Some(name) = game.pending_names.remove(stream_id) {
+                    game.names.insert(stream_id.to_string(), name.clone());
+                    match game.player(&name) {
+                        // A player who lost its connection took its slot back
+                        Some(player) => {
+                            let lost_stream_id =
+                                std::mem::replace(&mut player.stream_id, stream_id.to_string());
+                            game.challenges.remove(&lost_stream_id);
+                        }
+                        None => game.players.push(new_player(name, stream_id)),
+                    }
+                }
+            }
+            (Message::Challenge(challenge), true) => {
+                self.game(&room)
+                    .challenges
+                    .insert(stream_id.to_string(), challenge.clone());
+            }
+            (Message::ChallengeResult(challenge_result), false) => {
+                let game = self.game(&room);
+                // Answers to no challenge were ignored by the server
+                if let Some(challenge) = game.challenges.remove(stream_id) {
+                    let name = game
+                        .names
+                        .get(stream_id)
+                        .cloned()
+                        .unwrap_or_else(|| stream_id.to_string());
+                    let valid = challenges::verify_answer(&challenge, &challenge_result.answer);
+                    game.verified_answers.push_back((name.clone(), valid));
+                    if let (
+                        Challenge::MonstrousMaze(input),
+                        ChallengeAnswer::MonstrousMaze(output),
+                    ) = (challenge, &challenge_result.answer)
+                    {
+                        self.maze_answers.push(MazeAnswer {
+                            room: room.clone(),
+                            round: entry.round,
+                            name,
+                            input,
+                            output: output.clone(),
+                            valid,
+                        });
+                    }
+                }
+            }
+            (Message::RoundSummary(round_summary), true) => {
+                let game = self.game(&room);
+                if !game.is_copy(&entry.message) {
+                    divergences = game.check_round_summary(round_summary);
+                }
+            }
+            (Message::PublicLeaderBoard(leader_board), true) => {
+                let game = self.game(&room);
+                if !game.is_copy(&entry.message) {
+                    divergences = game.check_leader_board(leader_board, true);
+                }
+            }
+            (Message::EndOfGame(end_of_game), true) => {
+                let game = self.game(&room);
+                if !game.is_copy(&entry.message) {
+                    divergences = game.check_leader_board(&end_of_game.leader_board, false);
+                    game.finished = true;
+                }
+            }
+            _ => {}
+        }
+        self.divergences
+            .extend(divergences.into_iter().map(|description| Divergence {
+                room: room.clone(),
+                round: entry.round,
+                description,
+            }));
+    }
+
+    /// Game in progress in `room`
+    fn game(&mut self, room: &str) -> &mut GameReplay {
+        let index = match self.games.iter().rposition(|(name, _)| name == room) {
+            Some(index) => index,
+            None => {
+                self.games.push((room.to_string(), GameReplay::default()));
+                self.games.len() - 1
+            }
+        };
+        &mut self.games[index].1
+    }
+
+    /// Game a new player joins in `room`: a finished game is replaced by a new one
+    fn next_game(&mut self, room: &str) -> &mut GameReplay {
+        if self.game(room).finished {
+            self.games.push((room.to_string(), GameReplay::default()));
+        }
+        self.game(room)
+    }
+}
+
+impl GameReplay {
+    fn is_copy(&mut self, message: &Message) -> bool {
+        let serialized = serde_json::to_string(message).ok();
+        if serialized.is_some() && serialized == self.last_broadcast {
+            return true;
+        }
+        self.last_broadcast = serialized;
+        false
+    }
+
+    fn check_round_summary(&mut self, round_summary: &RoundSummary) -> Vec<String> {
+        let mut divergences = Vec::new();
+        for reported in round_summary.chain.iter() {
+            let (reported_success, used_time) = match &reported.value {
+                ChallengeValue::Ok { used_time, .. } => (true, *used_time),
+                ChallengeValue::BadResult { used_time, .. } => (false, *used_time),
+                ChallengeValue::Timeout => (false, 0.0),
+                ChallengeValue::Unreachable => {
+                    match self.player(&reported.name) {
+                        Some(player) => player.is_active = false,
+                        None => divergences.push(format!(
+                            "RoundSummary reports unknown player {}",
+                            reported.name
+                        )),
+                    }
+                    continue;
+                }
+            };
+            let verified = self
+                .verified_answers
+                .iter()
+                .position(|(name, _)| *name == reported.name)
+                .and_then(|index| self.verified_answers.remove(index));
+            let success = match verified {
+                Some((_, valid)) => {
+                    if valid != reported_success {
+                        divergences.push(format!(
+                            "answer of {} is {} but was reported {:?}",
+                            reported.name,
+                            if valid { "valid" } else { "invalid" },
+                            reported.value
+                        ));
+                    }
+                    valid
+                }
+                None => reported_success,
+            };
+            let timed_out = matches!(reported.value, ChallengeValue::Timeout);
+            match self.player(&reported.name) {
+                Some(player) => {
+                    score_answer(player, success, used_time);
+                    // A timed out player leaves the game, its late answer is ignored
+                    if timed_out {
+                        player.is_active = false;
+                        let stream_id = player.stream_id.clone();
+                        self.challenges.remove(&stream_id);
+                    }
+                }
+                None => divergences.push(format!(
+                    "RoundSummary reports unknown player {}",
+                    reported.name
+                )),
+            }
+            self.started = true;
+        }
+        for (name, _) in self.verified_answers.drain(..) {
+            divergences.push(format!("answer of {} is missing from RoundSummary", name));
+        }
+        divergences
+    }
+
+    /// Compares a broadcast leader board to the replay. With `resync`, the replay then
+    /// takes the broadcast values so that one divergence is only reported once.
+    fn check_leader_board(
+        &mut self,
+        leader_board: &PublicLeaderBoard,
+        resync: bool,
+    ) -> Vec<String> {
+        let mut divergences = Vec::new();
+        for expected in self.players.iter() {
+            if !leader_board
+                .0
+                .iter()
+                .any(|player| player.name == expected.name)
+            {
+                divergences.push(format!(
+                    "{} is missing from the leader board",
+                    expected.name
+                ));
+            }
+        }
+        for broadcast in leader_board.0.iter() {
+            let started = self.started;
+            let expected = match self.player(&broadcast.name) {
+                Some(expected) => expected,
+                None => {
+                    // Client transcripts only learn the other players from the first board
+                    if started {
+                        divergences.push(format!(
+                            "{} is on the leader board but never subscribed",
+                            broadcast.name
+                        ));
+                    }
+                    self.players.push(broadcast.clone());
+                    continue;
+                }
+            };
+            if expected.score != broadcast.score {
+                divergences.push(format!(
+                    "score of {}: expected {}, broadcast {}",
+                    broadcast.name, expected.score, broadcast.score
+                ));
+            }
+            if expected.steps != broadcast.steps {
+                divergences.push(format!(
+                    "steps of {}: expected {}, broadcast {}",
+                    broadcast.name, expected.steps, broadcast.steps
+                ));
+            }
+            if (expected.total_used_time - broadcast.total_used_time).abs() > USED_TIME_TOLERANCE {
+                divergences.push(format!(
+                    "total_used_time of {}: expected {}, broadcast {}",
+                    broadcast.name, expected.total_used_time, broadcast.total_used_time
+                ));
+            }
+            // Players may leave without a RoundSummary, but never come back
+            if !expected.is_active && broadcast.is_active {
+                divergences.push(format!(
+                    "{} is broadcast active after being reported Unreachable",
+                    broadcast.name
+                ));
+            }
+            if resync {
+                *expected = broadcast.clone();
+            }
+        }
+        divergences
+    }
+
+    fn player(&mut self, name: &str) -> Option<&mut PublicPlayer> {
+        self.players.iter_mut().find(|player| player.name == name)
+    }
+}
+
+fn new_player(name: String, stream_id: &str) -> PublicPlayer {
+    PublicPlayer {
+        name,
+        stream_id: stream_id.to_string(),
+        score: 0,
+        steps: 0,
+        is_active: true,
+        total_used_time: 0.0,
+    }
+}
+
+/// Scoring rules of the game: every answer is a step, a bad one costs a point.
+fn score_answer(player: &mut PublicPlayer, success: bool, used_time: f64) {
+    player.steps += 1;
+    if !success {
+        player.score -= 1;
+    }
+    player.total_used_time += used_time;
+}
+
+#[cfg(test)]
+mod replay_tests {
+    use crate::replay::Replay;
+    use shared::transcript::{Direction, TranscriptEntry};
+    use shared::{
+        Challenge, ChallengeAnswer, ChallengeResult, ChallengeValue, EndOfGame, MD5HashCashInput,
+        MD5HashCashOutput, Message, MonstrousMazeInput, MonstrousMazeOutput, PublicLeaderBoard,
+        PublicPlayer, ReportedChallengeResult, RoundSummary, Subscribe, SubscribeResult,
+    };
+
+    const FREE_POTATO: &str = "127.0.0.1:1000";
+    const DARK_SALAD: &str = "127.0.0.1:2000";
+
+    fn entry(
+        direction: Direction,
+        stream_id: &str,
+        round: u32,
+        message: Message,
+    ) -> TranscriptEntry {
+        TranscriptEntry {
+            timestamp: 0.0,
+            direction,
+            stream_id: stream_id.to_string(),
+            round,
+            message,
+        }
+    }
+
+    fn subscribe(stream_id: &str, name: &str) -> Vec<TranscriptEntry> {
+        vec![
+            entry(Direction::Received, stream_id, 0, Message::Hello),
+            entry(
+                Direction::Received,
+                stream_id,
+                0,
+                Message::Subscribe(Subscribe {
+                    name: name.to_string(),
+                }),
+            ),
+            entry(
+                Direction::Sent,
+                stream_id,
+                0,
+                Message::SubscribeResult(SubscribeResult::Ok),
+            ),
+        ]
+    }
+
+    fn challenge() -> Message {
+        Message::Challenge(Challenge::MD5HashCash(MD5HashCashInput {
+            complexity: 9,
+            message: "hello".to_string(),
+        }))
+    }
+
+    fn answer(seed: u64) -> Message {
+        Message::ChallengeResult(ChallengeResult {
+            answer: ChallengeAnswer::MD5HashCash(MD5HashCashOutput {
+                seed,
+                hashcode: "00441745D9BDF8E5D3C7872AC9DBB2C3".to_string(),
+            }),
+            next_target: "dark_salad".to_string(),
+        })
+    }
+
+    fn summary(value: ChallengeValue) -> Message {
+        Message::RoundSummary(RoundSummary {
+            challenge: "MD5HashCash".to_string(),
+            chain: vec![ReportedChallengeResult {
+                name: "free_potato".to_string(),
+                value,
+            }],
+        })
+    }
+
+    fn player(name: &str, stream_id: &str, score: i32, steps: u32, used_time: f64) -> PublicPlayer {
+        PublicPlayer {
+            name: name.to_string(),
+            stream_id: stream_id.to_string(),
+            score,
+            steps,
+            is_active: true,
+            total_used_time: used_time,
+        }
+    }
+
+    /// Server transcript of a one-round game where free_potato answers with `seed`
+    fn one_round_game(
+        seed: u64,
+        reported: ChallengeValue,
+        end: Vec<PublicPlayer>,
+    ) -> Vec<TranscriptEntry> {
+        let mut entries = subscribe(FREE_POTATO, "free_potato");
+        entries.extend(subscribe(DARK_SALAD, "dark_salad"));
+        entries.extend([
+            entry(Direction::Sent, FREE_POTATO, 1, challenge()),
+            entry(Direction::Received, FREE_POTATO, 1, answer(seed)),
+            entry(Direction::Sent, FREE_POTATO, 1, summary(reported.clone())),
+            entry(Direction::Sent, DARK_SALAD, 1, summary(reported)),
+            entry(
+                Direction::Sent,
+                FREE_POTATO,
+                2,
+                Message::EndOfGame(EndOfGame {
+                    leader_board: PublicLeaderBoard(end),
+                }),
+            ),
+        ]);
+        entries
+    }
+
+    #[test]
+    fn consistent_game_should_have_no_divergence() {
+        let entries = one_round_game(
+            844,
+            ChallengeValue::Ok {
+                used_time: 0.5,
+                next_target: "dark_salad".to_string(),
+            },
+            vec![
+                player("free_potato", FREE_POTATO, 0, 1, 0.5),
+                player("dark_salad", DARK_SALAD, 0, 0, 0.0),
+            ],
+        );
+        let replay = Replay::run(&entries);
+        assert_eq!(replay.divergences(), &[]);
+        let games = replay.games();
+        assert_eq!(games.len(), 1);
+        assert!(games[0].finished);
+        assert_eq!(games[0].leader_board.0[0].steps, 1);
+    }
+
+    #[test]
+    fn maze_answers_should_be_kept_with_their_maze() {
+        let maze_input = MonstrousMazeInput {
+            endurance: 2,
+            grid: "|I  X|".to_string(),
+        };
+        let maze_answer = |path: &str| {
+            Message::ChallengeResult(ChallengeResult {
+                answer: ChallengeAnswer::MonstrousMaze(MonstrousMazeOutput {
+                    path: path.to_string(),
+                }),
+                next_target: "dark_salad".to_string(),
+            })
+        };
+        let mut entries = subscribe(FREE_POTATO, "free_potato");
+        entries.extend([
+            entry(
+                Direction::Sent,
+                FREE_POTATO,
+                1,
+                Message::Challenge(Challenge::MonstrousMaze(maze_input.clone())),
+            ),
+            entry(Direction::Received, FREE_POTATO, 1, maze_answer(">>")),
+            entry(Direction::Sent, FREE_POTATO, 2, challenge()),
+            entry(Direction::Received, FREE_POTATO, 2, answer(844)),
+        ]);
+        let replay = Replay::run(&entries);
+        let maze_answers = replay.maze_answers();
+        assert_eq!(maze_answers.len(), 1);
+        assert_eq!(maze_answers[0].name, "free_potato");
+        assert_eq!(maze_answers[0].round, 1);
+        assert_eq!(maze_answers[0].input.grid, maze_input.grid);
+        assert_eq!(maze_answers[0].output.path, ">>");
+        assert!(!maze_answers[0].valid);
+    }
+
+    #[test]
+    fn reconnected_player_should_keep_its_slot() {
+        const FREE_POTATO_AGAIN: &str = "127.0.0.1:1001";
+        let mut entries = subscribe(FREE_POTATO, "free_potato");
+        entries.extend(subscribe(DARK_SALAD, "dark_salad"));
+        entries.push(entry(Direction::Sent, FREE_POTATO, 1, challenge()));
+        entries.extend(subscribe(FREE_POTATO_AGAIN, "free_potato"));
+        entries.extend([
+            entry(Direction::Sent, FREE_POTATO_AGAIN, 1, challenge()),
+            entry(Direction::Received, FREE_POTATO_AGAIN, 1, answer(844)),
+            entry(
+                Direction::Sent,
+                FREE_POTATO_AGAIN,
+                1,
+                summary(ChallengeValue::Ok {
+                    used_time: 0.5,
+                    next_target: "dark_salad".to_string(),
+                }),
+            ),
+            entry(
+                Direction::Sent,
+                FREE_POTATO_AGAIN,
+                2,
+                Message::EndOfGame(EndOfGame {
+                    leader_board: PublicLeaderBoard(vec![
+                        player("free_potato", FREE_POTATO_AGAIN, 0, 1, 0.5),
+                        player("dark_salad", DARK_SALAD, 0, 0, 0.0),
+                    ]),
+                }),
+            ),
+        ]);
+        let replay = Replay::run(&entries);
+        assert_eq!(replay.divergences(), &[]);
+        let leader_board = &replay.games()[0].leader_board.0;
+        assert_eq!(leader_board.len(), 2);
+        assert_eq!(leader_board[0].stream_id, FREE_POTATO_AGAIN);
+    }
+
+    #[test]
+    fn late_answer_after_timeout_should_be_ignored() {
+        let mut timed_out = player("free_potato", FREE_POTATO, -1, 1, 0.0);
+        timed_out.is_active = false;
+        let mut entries = subscribe(FREE_POTATO, "free_potato");
+        entries.extend(subscribe(DARK_SALAD, "dark_salad"));
+        entries.extend([
+            entry(Direction::Sent, FREE_POTATO, 1, challenge()),
+            entry(
+                Direction::Sent,
+                FREE_POTATO,
+                1,
+                summary(ChallengeValue::Timeout),
+            ),
+            entry(Direction::Sent, DARK_SALAD, 2, challenge()),
+            entry(Direction::Received, FREE_POTATO, 2, answer(844)),
+            entry(Direction::Received, DARK_SALAD, 2, answer(844)),
+            entry(
+                Direction::Sent,
+                DARK_SALAD,
+                2,
+                Message::RoundSummary(RoundSummary {
+                    challenge: "MD5HashCash".to_string(),
+                    chain: vec![ReportedChallengeResult {
+                        name: "dark_salad".to_string(),
+                        value: ChallengeValue::Ok {
+                            used_time: 0.5,
+                            next_target: "dark_salad".to_string(),
+                        },
+                    }],
+                }),
+            ),
+            entry(
+                Direction::Sent,
+                FREE_POTATO,
+                3,
+                Message::EndOfGame(EndOfGame {
+                    leader_board: PublicLeaderBoard(vec![
+                        timed_out,
+                        player("dark_salad", DARK_SALAD, 0, 1, 0.5),
+                    ]),
+                }),
+            ),
+        ]);
+        assert_eq!(Replay::run(&entries).divergences(), &[]);
+    }
+
+    #[test]
+    fn invalid_answer_reported_ok_should_diverge() {
+        let entries = one_round_game(
+            1,
+            ChallengeValue::Ok {
+                used_time: 0.5,
+                next_target: "dark_salad".to_string(),
+            },
+            vec![
+                player("free_potato", FREE_POTATO, 0, 1, 0.5),
+                player("dark_salad", DARK_SALAD, 0, 0, 0.0),
+            ],
+        );
+        let divergences = Replay::run(&entries).divergences().to_vec();
+        assert_eq!(divergences.len(), 2);
+        assert!(divergences[0].description.contains("invalid"));
+        assert_eq!(
+            divergences[1].description,
+            "score of free_potato: expected -1, broadcast 0"
+        );
+    }
+
+    #[test]
+    fn lost_used_time_should_diverge() {
+        let entries = one_round_game(
+            1,
+            ChallengeValue::BadResult {
+                used_time: 0.5,
+                next_target: "dark_salad".to_string(),
+            },
+            vec![
+                player("free_potato", FREE_POTATO, -1, 1, 0.0),
+                player("dark_salad", DARK_SALAD, 0, 0, 0.0),
+            ],
+        );
+        let divergences = Replay::run(&entries).divergences().to_vec();
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].round, 2);
+        assert!(divergences[0].description.starts_with("total_used_time"));
+    }
+
+    #[test]
+    fn client_transcript_should_be_replayed_from_the_client_side() {
+        let server = "127.0.0.1:7878";
+        let entries = vec![
+            entry(Direction::Sent, server, 0, Message::Hello),
+            entry(
+                Direction::Sent,
+                server,
+                0,
+                Message::Subscribe(Subscribe {
+                    name: "free_potato".to_string(),
+                }),
+            ),
+            entry(
+                Direction::Received,
+                server,
+                0,
+                Message::SubscribeResult(SubscribeResult::Ok),
+            ),
+            entry(
+                Direction::Received,
+                server,
+                1,
+                Message::PublicLeaderBoard(PublicLeaderBoard(vec![
+                    player("free_potato", FREE_POTATO, 0, 0, 0.0),
+                    player("dark_salad", DARK_SALAD, 0, 0, 0.0),
+                ])),
+            ),
+            entry(Direction::Received, server, 1, challenge()),
+            entry(Direction::Sent, server, 1, answer(844)),
+            entry(
+                Direction::Received,
+                server,
+                1,
+                summary(ChallengeValue::BadResult {
+                    used_time: 0.5,
+                    next_target: "dark_salad".to_string(),
+                }),
+            ),
+        ];
+        let divergences = Replay::run(&entries).divergences().to_vec();
+        assert_eq!(divergences.len(), 1);
+        assert!(divergences[0].description.contains("is valid"));
+    }
+}
@@ -0,0 +1,641 @@
+use crate::game::{GameConfig, GamePhase};
+use crate::lobby::{Lobby, CLOSE_GRACE_PERIOD};
+use rand::Rng;
+use shared::challenges::hash_cash::MD5HashCash as MD5HashCashChallenge;
+use shared::challenges::monstrous_maze::MonstrousMaze as MonstrousMazeChallenge;
+use shared::challenges::Challenge as _;
+use shared::{
+    Challenge, ChallengeAnswer, ChallengeResult, MD5HashCashOutput, Message, MonstrousMazeOutput,
+    PublicPlayer, Subscribe, SubscribeResult, MAX_MESSAGE_SIZE,
+};
+use std::io::Write;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time bots and the harness wait for the server before giving up
+const SERVER_TIMEOUT: Duration = Duration::from_secs(10);
+/// Polling interval while waiting for every bot to be registered
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// Target of [`Behaviour::UnknownTarget`], which no bot is named
+pub const UNKNOWN_TARGET: &str = "no such player";
+
+/// How a bot answers the challenges it receives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behaviour {
+    /// Solves every challenge
+    Honest,
+    /// Answers every challenge with a wrong solution
+    WrongAnswer,
+    /// Closes its connection on its first challenge, without answering
+    DisconnectOnChallenge,
+    /// Sends a frame larger than [`MAX_MESSAGE_SIZE`] instead of its first answer
+    OversizedFrame,
+    /// Sends half of a length prefix instead of its first answer, then stops writing
+    TruncatedPrefix,
+    /// Answers every MonstrousMaze with a path of invalid characters, solves MD5HashCash
+    InvalidPath,
+    /// Answers every challenge with an answer to the other kind of challenge
+    WrongChallenge,
+    /// Solves every challenge but passes the potato to [`UNKNOWN_TARGET`]
+    UnknownTarget,
+}
+
+impl Behaviour {
+    /// Whether the bot leaves the game at its first challenge
+    pub fn leaves_on_challenge(&self) -> bool {
+        matches!(
+            self,
+            Behaviour::DisconnectOnChallenge
+                | Behaviour::OversizedFrame
+                | Behaviour::TruncatedPrefix
+        )
+    }
+}
+
+/// Simulated client: a name, a behaviour and the time it takes to answer
+#[derive(Debug, Clone)]
+pub struct Bot {
+    pub name: String,
+    pub behaviour: Behaviour,
+    pub think_time: Duration,
+    /// Player the potato is passed to, a random one if `None`
+    pub target: Option<String>,
+}
+
+impl Bot {
+    pub fn new(name: &str, behaviour: Behaviour) -> Bot {
+        Bot {
+            name: name.to_string(),
+            behaviour,
+            think_time: Duration::ZERO,
+            target: None,
+        }
+    }
+
+    /// Same bot, waiting `think_time` before each answer (a slow solver or a late answer)
+    pub fn thinking(mut self, think_time: Duration) -> Bot {
+        self.think_time = think_time;
+        self
+    }
+
+    /// Same bot, always passing the potato to `target`
+    pub fn targeting(mut self, target: &str) -> Bot {
+        self.target = Some(target.to_string());
+        self
+    }
+}
+
+/// How the game ended for a bot
+#[derive(Debug)]
+pub enum Outcome {
+    /// `EndOfGame` was received, with this leader board
+    Finished(Vec<PublicPlayer>),
+    /// The bot left during the game, as its behaviour asked
+    Disconnected,
+    /// The connection broke or the server sent something unexpected
+    Failed(String),
+}
+
+/// Server running in the test process, on an ephemeral port of the loopback interface.
+pub struct BotServer {
+    addr: String,
+    lobby: Arc<Lobby>,
+}
+
+impl BotServer {
+    pub fn start(rounds: u32) -> BotServer {
+        BotServer::start_with_config(GameConfig {
+            rounds,
+            ..GameConfig::default()
+        })
+    }
+
+    pub fn start_with_config(config: GameConfig) -> BotServer {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Cannot bind the bot server");
+        let addr = listener
+            .local_addr()
+            .expect("Bot server without address")
+            .to_string();
+        let lobby = Arc::new(Lobby::new(config, 1));
+        let listener_lobby = Arc::clone(&lobby);
+        thread::spawn(move || crate::accept_connections(listener, &listener_lobby));
+        // Like the main thread of the server, which also times out slow players
+        let room = lobby.default_room();
+        thread::spawn(move || drop(room.wait_for_end_of_game(CLOSE_GRACE_PERIOD)));
+        BotServer { addr, lobby }
+    }
+
+    /// Connects every bot, starts the game once they are all registered and returns
+    /// their outcomes, in the order of `bots`.
+    pub fn play(&self, bots: &[Bot]) -> Vec<Outcome> {
+        let handles: Vec<_> = bots
+            .iter()
+            .cloned()
+            .map(|bot| {
+                let addr = self.addr.clone();
+                thread::spawn(move || run_bot(&addr, &bot))
+            })
+            .collect();
+
+        let game = self.lobby.default_room();
+        let deadline = Instant::now() + SERVER_TIMEOUT;
+        while game.lock().public_players.len() < bots.len() {
+            assert!(Instant::now() < deadline, "Bots were not all registered");
+            thread::sleep(POLL_INTERVAL);
+        }
+        assert!(game.lock().start_game(), "The game did not start");
+        game.notify();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Outcome::Failed("Bot panicked".to_string()))
+            })
+            .collect()
+    }
+
+    /// Waits for the game to end; `false` if it is still running after [`SERVER_TIMEOUT`].
+    pub fn wait_for_the_end(&self) -> bool {
+        let game = self.lobby.default_room();
+        let deadline = Instant::now() + SERVER_TIMEOUT;
+        while game.lock().phase != GamePhase::TheEnd {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        true
+    }
+}
+
+fn run_bot(addr: &str, bot: &Bot) -> Outcome {
+    match play_bot(addr, bot) {
+        Ok(outcome) => outcome,
+        Err(err) => Outcome::Failed(format!("{}: {}", bot.name, err)),
+    }
+}
+
+fn play_bot(addr: &str, bot: &Bot) -> std::io::Result<Outcome> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(SERVER_TIMEOUT))?;
+    shared::write_message(&stream, Message::Hello)?;
+    shared::receive_message(&stream)?;
+    shared::write_message(
+        &stream,
+        Message::Subscribe(Subscribe {
+            name: bot.name.clone(),
+        }),
+    )?;
+    match shared::receive_message(&stream)? {
+        Message::SubscribeResult(SubscribeResult::Ok) => {}
+        message => return Ok(Outcome::Failed(format!("not subscribed: {:?}", message))),
+    }
+
+    let mut names = Vec::new();
+    loop {
+        match shared::receive_message(&stream)? {
+            Message::PublicLeaderBoard(leader_board) => {
+                names = leader_board
+                    .0
+                    .into_iter()
+                    .map(|player| player.name)
+                    .collect();
+            }
+            Message::Challenge(challenge) => {
+                let answer = match bot.behaviour {
+                    Behaviour::Honest | Behaviour::UnknownTarget => solve(challenge),
+                    Behaviour::WrongAnswer => wrong_answer(challenge),
+                    Behaviour::InvalidPath => invalid_path(challenge),
+                    Behaviour::WrongChallenge => other_challenge_answer(challenge),
+                    Behaviour::DisconnectOnChallenge => {
+                        stream.shutdown(Shutdown::Both)?;
+                        return Ok(Outcome::Disconnected);
+                    }
+                    Behaviour::OversizedFrame => {
+                        (&stream).write_all(&(MAX_MESSAGE_SIZE as u32 + 1).to_be_bytes())?;
+                        (&stream).write_all(b"{}")?;
+                        return Ok(wait_for_close(&stream));
+                    }
+                    Behaviour::TruncatedPrefix => {
+                        (&stream).write_all(&[0, 0])?;
+                        stream.shutdown(Shutdown::Write)?;
+                        return Ok(wait_for_close(&stream));
+                    }
+                };
+                thread::sleep(bot.think_time);
+                let next_target = match (&bot.target, bot.behaviour) {
+                    (_, Behaviour::UnknownTarget) => UNKNOWN_TARGET.to_string(),
+                    (Some(target), _) => target.clone(),
+                    (None, _) if names.is_empty() => String::new(),
+                    (None, _) => names[rand::thread_rng().gen_range(0..names.len())].clone(),
+                };
+                shared::write_message(
+                    &stream,
+                    Message::ChallengeResult(ChallengeResult {
+                        answer,
+                        next_target,
+                    }),
+                )?;
+            }
+            Message::EndOfGame(end_of_game) => {
+                stream.shutdown(Shutdown::Both)?;
+                return Ok(Outcome::Finished(end_of_game.leader_board.0));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn solve(challenge: Challenge) -> ChallengeAnswer {
+    match challenge {
+        Challenge::MD5HashCash(input) => {
+            ChallengeAnswer::MD5HashCash(MD5HashCashChallenge::new(input).solve())
+        }
+        Challenge::MonstrousMaze(input) => {
+            ChallengeAnswer::MonstrousMaze(MonstrousMazeChallenge::new(input).solve())
+        }
+    }
+}
+
+/// Reads until the server closes the connection of a bot that sent a broken frame
+fn wait_for_close(stream: &TcpStream) -> Outcome {
+    while shared::receive_message(stream).is_ok() {}
+    Outcome::Disconnected
+}
+
+/// Path of invalid characters for a MonstrousMaze, the solution for MD5HashCash
+fn invalid_path(challenge: Challenge) -> ChallengeAnswer {
+    match solve(challenge) {
+        ChallengeAnswer::MonstrousMaze(mut output) => {
+            output.path.insert(0, '?');
+            ChallengeAnswer::MonstrousMaze(output)
+        }
+        answer => answer,
+    }
+}
+
+/// Answer of the other type than `challenge`
+fn other_challenge_answer(challenge: Challenge) -> ChallengeAnswer {
+    match challenge {
+        Challenge::MD5HashCash(_) => ChallengeAnswer::MonstrousMaze(MonstrousMazeOutput {
+            path: ">".to_string(),
+        }),
+        Challenge::MonstrousMaze(_) => ChallengeAnswer::MD5HashCash(MD5HashCashOutput {
+            seed: 0,
+            hashcode: "0".repeat(32),
+        }),
+    }
+}
+
+/// Answer of the right type that never verifies
+fn wrong_answer(challenge: Challenge) -> ChallengeAnswer {
+    match challenge {
+        Challenge::MD5HashCash(_) => ChallengeAnswer::MD5HashCash(MD5HashCashOutput {
+            seed: 0,
+            hashcode: "0".repeat(32),
+        }),
+        Challenge::MonstrousMaze(_) => ChallengeAnswer::MonstrousMaze(MonstrousMazeOutput {
+            path: String::new(),
+        }),
+    }
+}
+
+/// Checks the final leader boards against the bots that played and the rounds of the game.
+///
+/// Every bot that finished saw the same leader board; each bot appears on it exactly
+/// once; only bots that left are inactive; scores match the behaviours; every round
+/// was either answered or lost by a bot that left holding the potato.
+pub fn check_end_of_game(bots: &[Bot], outcomes: &[Outcome], rounds: u32) -> Result<(), String> {
+    let mut leader_boards = Vec::new();
+    for (bot, outcome) in bots.iter().zip(outcomes) {
+        match (bot.behaviour, outcome) {
+            (behaviour, Outcome::Disconnected) if behaviour.leaves_on_challenge() => {}
+            (_, Outcome::Finished(leader_board)) => leader_boards.push(leader_board),
+            (_, Outcome::Failed(err)) => return Err(err.clone()),
+            (_, outcome) => return Err(format!("{}: unexpected outcome {:?}", bot.name, outcome)),
+        }
+    }
+    let leader_board = match leader_boards.first() {
+        Some(leader_board) => *leader_board,
+        None => return Ok(()),
+    };
+    let same_as_first = |other: &&Vec<PublicPlayer>| {
+        serde_json::to_value(other).ok() == serde_json::to_value(leader_board).ok()
+    };
+    if !leader_boards.iter().all(same_as_first) {
+        return Err("Bots received different final leader boards".to_string());
+    }
+    if leader_board.len() != bots.len() {
+        return Err(format!(
+            "{} players on the leader board for {} bots",
+            leader_board.len(),
+            bots.len()
+        ));
+    }
+
+    let mut unreachable_rounds = 0;
+    for (bot, outcome) in bots.iter().zip(outcomes) {
+        let player = leader_board
+            .iter()
+            .find(|player| player.name == bot.name)
+            .ok_or_else(|| format!("{} is not on the leader board", bot.name))?;
+        let left = matches!(outcome, Outcome::Disconnected);
+        if player.is_active == left {
+            return Err(format!("{} has the wrong activity: {:?}", bot.name, player));
+        }
+        // An invalid path only loses the MonstrousMaze rounds
+        let expected_score = match bot.behaviour {
+            Behaviour::WrongAnswer | Behaviour::WrongChallenge => Some(-(player.steps as i32)),
+            Behaviour::InvalidPath => None,
+            _ => Some(0),
+        };
+        if let Some(expected_score) = expected_score {
+            if player.score != expected_score {
+                return Err(format!(
+                    "{} should score {}: {:?}",
+                    bot.name, expected_score, player
+                ));
+            }
+        }
+        let min_used_time = bot.think_time.as_secs_f64() * player.steps as f64;
+        if player.total_used_time < min_used_time {
+            return Err(format!(
+                "{} should use {}s: {:?}",
+                bot.name, min_used_time, player
+            ));
+        }
+        if left {
+            unreachable_rounds += 1;
+        }
+    }
+    let steps: u32 = leader_board.iter().map(|player| player.steps).sum();
+    if steps + unreachable_rounds != rounds {
+        return Err(format!(
+            "{} answered and {} unreachable rounds for {} rounds",
+            steps, unreachable_rounds, rounds
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod bots_tests {
+    use crate::bots::{check_end_of_game, Behaviour, Bot, BotServer, Outcome, UNKNOWN_TARGET};
+    use crate::game::{GameConfig, GamePhase};
+    use shared::{AdminLogin, ChallengeValue, Message, RoundSummary, Subscribe, SubscribeResult};
+    use std::net::TcpStream;
+    use std::time::Duration;
+
+    fn admin(server: &BotServer, token: &str) -> TcpStream {
+        let admin = TcpStream::connect(&server.addr).unwrap();
+        shared::write_message(
+            &admin,
+            Message::AdminLogin(AdminLogin {
+                token: token.to_string(),
+            }),
+        )
+        .unwrap();
+        assert!(matches!(
+            shared::receive_message(&admin),
+            Ok(Message::SubscribeResult(SubscribeResult::Ok))
+        ));
+        admin
+    }
+
+    fn honest_bots(count: usize) -> Vec<Bot> {
+        (0..count)
+            .map(|index| Bot::new(&format!("bot-{}", index), Behaviour::Honest))
+            .collect()
+    }
+
+    #[test]
+    fn honest_bots_should_play_every_round() {
+        let server = BotServer::start(30);
+        let bots = honest_bots(8);
+
+        let outcomes = server.play(&bots);
+        check_end_of_game(&bots, &outcomes, 30).unwrap();
+        assert!(server.wait_for_the_end());
+    }
+
+    #[test]
+    fn wrong_and_late_answers_should_be_scored() {
+        let server = BotServer::start(20);
+        let mut bots = honest_bots(3);
+        bots.push(Bot::new("liar", Behaviour::WrongAnswer));
+        bots.push(Bot::new("sloth", Behaviour::Honest).thinking(Duration::from_millis(20)));
+        bots.push(
+            Bot::new("late_liar", Behaviour::WrongAnswer).thinking(Duration::from_millis(20)),
+        );
+
+        let outcomes = server.play(&bots);
+        check_end_of_game(&bots, &outcomes, 20).unwrap();
+    }
+
+    #[test]
+    fn game_should_go_on_when_bots_leave_during_a_challenge() {
+        let server = BotServer::start(20);
+        let mut bots = honest_bots(3);
+        bots.push(Bot::new("quitter", Behaviour::DisconnectOnChallenge));
+        bots.push(Bot::new("deserter", Behaviour::DisconnectOnChallenge));
+        bots.push(Bot::new("liar", Behaviour::WrongAnswer));
+
+        // A quitter never challenged plays until the end, like an honest bot
+        let outcomes = server.play(&bots);
+        check_end_of_game(&bots, &outcomes, 20).unwrap();
+    }
+
+    #[test]
+    fn game_should_end_when_every_bot_left() {
+        let server = BotServer::start(20);
+        let bots = vec![
+            Bot::new("quitter", Behaviour::DisconnectOnChallenge),
+            Bot::new("deserter", Behaviour::DisconnectOnChallenge),
+        ];
+
+        let outcomes = server.play(&bots);
+        assert!(outcomes
+            .iter()
+            .all(|outcome| matches!(outcome, Outcome::Disconnected)));
+        assert!(server.wait_for_the_end());
+    }
+
+    #[test]
+    fn unauthenticated_abort_game_should_be_refused() {
+        let server = BotServer::start_with_config(GameConfig {
+            admin_token: Some("s3cr3t".to_string()),
+            ..GameConfig::default()
+        });
+        let intruder = TcpStream::connect(&server.addr).unwrap();
+        shared::write_message(&intruder, Message::AbortGame).unwrap();
+        assert!(shared::receive_message(&intruder).is_err());
+        let wrong_token = TcpStream::connect(&server.addr).unwrap();
+        shared::write_message(
+            &wrong_token,
+            Message::AdminLogin(AdminLogin {
+                token: "guess".to_string(),
+            }),
+        )
+        .unwrap();
+        assert!(shared::receive_message(&wrong_token).is_err());
+        assert_eq!(server.lobby.default_room().lock().phase, GamePhase::Joining);
+
+        let admin = admin(&server, "s3cr3t");
+        shared::write_message(&admin, Message::AbortGame).unwrap();
+        assert!(server.wait_for_the_end());
+    }
+
+    #[test]
+    fn start_game_with_one_bot_should_be_refused() {
+        let server = BotServer::start_with_config(GameConfig {
+            admin_token: Some("s3cr3t".to_string()),
+            ..GameConfig::default()
+        });
+        let bot = TcpStream::connect(&server.addr).unwrap();
+        shared::write_message(&bot, Message::Hello).unwrap();
+        shared::receive_message(&bot).unwrap();
+        shared::write_message(
+            &bot,
+            Message::Subscribe(Subscribe {
+                name: "lonely".to_string(),
+            }),
+        )
+        .unwrap();
+        assert!(matches!(
+            shared::receive_message(&bot),
+            Ok(Message::SubscribeResult(SubscribeResult::Ok))
+        ));
+
+        let admin = admin(&server, "s3cr3t");
+        shared::write_message(&admin, Message::StartGame).unwrap();
+        match shared::receive_message(&admin) {
+            Ok(Message::PublicLeaderBoard(leader_board)) => {
+                assert_eq!(leader_board.0.len(), 1);
+                assert_eq!(leader_board.0[0].name, "lonely");
+            }
+            message => panic!("Refusal expected, got {:?}", message),
+        }
+        assert_eq!(server.lobby.default_room().lock().phase, GamePhase::Joining);
+    }
+
+    /// Plays a game between an honest bot passing it the potato and a `faulty` bot, and
+    /// returns the round summaries
+    fn play_against(behaviour: Behaviour, rounds: u32) -> Vec<RoundSummary> {
+        let server = BotServer::start(rounds);
+        let bots = vec![
+            Bot::new("honest", Behaviour::Honest).targeting("faulty"),
+            Bot::new("faulty", behaviour),
+        ];
+        let outcomes = server.play(&bots);
+        check_end_of_game(&bots, &outcomes, rounds).unwrap();
+        assert!(server.wait_for_the_end());
+
+        // The server still welcomes new connections
+        let newcomer = TcpStream::connect(&server.addr).unwrap();
+        shared::write_message(&newcomer, Message::Hello).unwrap();
+        assert!(matches!(
+            shared::receive_message(&newcomer),
+            Ok(Message::Welcome(..))
+        ));
+        let round_summaries = server.lobby.default_room().lock().round_summaries.clone();
+        round_summaries
+    }
+
+    /// Challenge name and value of each round `faulty` held the potato, with the name of
+    /// the player challenged in the next round if any
+    fn faulty_rounds(
+        round_summaries: &[RoundSummary],
+    ) -> Vec<(String, ChallengeValue, Option<String>)> {
+        round_summaries
+            .iter()
+            .enumerate()
+            .filter(|(_, round_summary)| round_summary.chain[0].name == "faulty")
+            .map(|(index, round_summary)| {
+                (
+                    round_summary.challenge.clone(),
+                    round_summary.chain[0].value.clone(),
+                    round_summaries
+                        .get(index + 1)
+                        .map(|next| next.chain[0].name.clone()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn broken_frames_should_make_the_bot_unreachable() {
+        for behaviour in [Behaviour::OversizedFrame, Behaviour::TruncatedPrefix] {
+            let rounds = faulty_rounds(&play_against(behaviour, 10));
+            assert_eq!(rounds.len(), 1, "{:?}", behaviour);
+            assert!(matches!(rounds[0].1, ChallengeValue::Unreachable));
+        }
+    }
+
+    #[test]
+    fn invalid_path_and_wrong_challenge_should_be_bad_results() {
+        let rounds = faulty_rounds(&play_against(Behaviour::InvalidPath, 40));
+        assert!(rounds
+            .iter()
+            .any(|(challenge, _, _)| challenge == "MonstrousMaze"));
+        for (challenge, value, _) in rounds {
+            match (challenge.as_str(), value) {
+                ("MonstrousMaze", ChallengeValue::BadResult { .. }) => {}
+                ("MD5HashCash", ChallengeValue::Ok { .. }) => {}
+                (challenge, value) => panic!("{} answered with {:?}", challenge, value),
+            }
+        }
+
+        let rounds = faulty_rounds(&play_against(Behaviour::WrongChallenge, 10));
+        assert!(!rounds.is_empty());
+        assert!(rounds
+            .iter()
+            .all(|(_, value, _)| matches!(value, ChallengeValue::BadResult { .. })));
+    }
+
+    #[test]
+    fn unknown_target_should_pass_the_potato_to_a_random_player() {
+        let rounds = faulty_rounds(&play_against(Behaviour::UnknownTarget, 10));
+        assert!(rounds.iter().any(|(_, _, next)| next.is_some()));
+        for (_, value, next) in rounds {
+            match value {
+                ChallengeValue::Ok { next_target, .. } => assert_eq!(next_target, UNKNOWN_TARGET),
+                value => panic!("Ok expected, got {:?}", value),
+            }
+            if let Some(next) = next {
+                assert!(next == "honest" || next == "faulty", "{}", next);
+            }
+        }
+    }
+
+    #[test]
+    fn late_bot_should_be_timed_out() {
+        let server = BotServer::start_with_config(GameConfig {
+            rounds: 10,
+            // Honest bots need up to a tenth of a second, more on a loaded machine
+            challenge_timeout: Duration::from_millis(500),
+            ..GameConfig::default()
+        });
+        let mut bots = honest_bots(2);
+        bots.push(Bot::new("sloth", Behaviour::Honest).thinking(Duration::from_millis(1000)));
+
+        let outcomes = server.play(&bots);
+        let leader_board = match &outcomes[2] {
+            Outcome::Finished(leader_board) => leader_board,
+            outcome => panic!("Unexpected outcome {:?}", outcome),
+        };
+        let sloth = leader_board
+            .iter()
+            .find(|player| player.name == "sloth")
+            .unwrap();
+        // Its first challenge, if any, times out and it is never challenged again
+        assert!(sloth.steps <= 1);
+        assert_eq!(sloth.score, -(sloth.steps as i32));
+        assert_eq!(sloth.is_active, sloth.steps == 0);
+        let steps: u32 = leader_board.iter().map(|player| player.steps).sum();
+        assert_eq!(steps, 10);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::game::{GamePhase, GameState};
+use shared::Message;
+
+/// Where a connection stands in the `Hello` → `Welcome` → `Subscribe` sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// TCP connection opened, waiting for `Hello`
+    Connected,
+    /// `Welcome` sent, waiting for a successful `Subscribe`
+    Welcomed,
+    /// Registered as a player
+    Subscribed,
+    /// Controller connection logged in with the admin token, may send `StartGame`,
+    /// `ListPlayers` or `AbortGame`
+    Admin,
+    /// Read-only connection that sent `Spectate`
+    Spectator,
+}
+
+/// What the server does with a message received on a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The message is expected and must be handled
+    Accept,
+    /// The message is out of sequence but harmless
+    Ignore,
+    /// The peer does not follow the protocol, the connection is closed
+    Disconnect,
+}
+
+impl ConnectionState {
+    /// Decides what to do with `message`, received from `stream_id`, in the current state.
+    ///
+    /// Connections that are not yet players are closed on the first unexpected message,
+    /// and on `Subscribe` once registrations are sealed, unless it takes back the slot of
+    /// a player who lost its connection. They may change rooms until they subscribe.
+    /// Players are never disconnected here: their out-of-sequence messages, including a
+    /// `ChallengeResult` while they do not hold the potato, are ignored. So is anything
+    /// sent by a spectator. Admin commands are only accepted after an `AdminLogin` with
+    /// the server admin token.
+    pub fn verdict(&self, message: &Message, game: &GameState, stream_id: &str) -> Verdict {
+        match (self, message) {
+            (ConnectionState::Connected, Message::Hello) => Verdict::Accept,
+            (
+                ConnectionState::Connected | ConnectionState::Welcomed | ConnectionState::Admin,
+                Message::JoinRoom(..),
+            ) => Verdict::Accept,
+            (ConnectionState::Connected, Message::AdminLogin(login))
+                if game.is_admin_token(&login.token) =>
+            {
+                Verdict::Accept
+            }
+            (ConnectionState::Admin, message) if is_admin_message(message) => Verdict::Accept,
+            (ConnectionState::Connected, _) => Verdict::Disconnect,
+            (ConnectionState::Welcomed, Message::Subscribe(subscribe))
+                if game.phase == GamePhase::Joining || game.can_reclaim(&subscribe.name) =>
+            {
+                Verdict::Accept
+            }
+            (ConnectionState::Welcomed, Message::Spectate) => Verdict::Accept,
+            (ConnectionState::Welcomed, Message::Hello) => Verdict::Ignore,
+            (ConnectionState::Welcomed, _) => Verdict::Disconnect,
+            (ConnectionState::Subscribed, Message::ChallengeResult(..))
+                if game.is_potato_holder(stream_id) =>
+            {
+                Verdict::Accept
+            }
+            (ConnectionState::Subscribed, _) => Verdict::Ignore,
+            (ConnectionState::Admin, _) => Verdict::Disconnect,
+            (ConnectionState::Spectator, _) => Verdict::Ignore,
+        }
+    }
+}
+
+fn is_admin_message(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::StartGame | Message::ListPlayers | Message::AbortGame
+    )
+}
+
+#[cfg(test)]
+mod connection_tests {
+    use crate::connection::{ConnectionState, Verdict};
+    use crate::game::{GameConfig, GamePhase, GameState};
+    use shared::{AdminLogin, JoinRoom, Message, Welcome};
+    use shared::{
+        ChallengeAnswer, ChallengeResult, MonstrousMazeOutput, Subscribe, SubscribeResult,
+    };
+    use std::net::{TcpListener, TcpStream};
+    use std::time::Duration;
+
+    const STREAM_ID: &str = "127.0.0.1:4242";
+
+    fn subscribe() -> Message {
+        Message::Subscribe(Subscribe {
+            name: "free_potato".to_string(),
+        })
+    }
+
+    fn challenge_result() -> Message {
+        Message::ChallengeResult(ChallengeResult {
+            answer: ChallengeAnswer::MonstrousMaze(MonstrousMazeOutput {
+                path: ">>>".to_string(),
+            }),
+            next_target: "dark_salad".to_string(),
+        })
+    }
+
+    fn loopback_stream() -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        TcpStream::connect(listener.local_addr().unwrap()).unwrap()
+    }
+
+    fn game() -> GameState {
+        GameState::new(GameConfig::default())
+    }
+
+    fn game_with_potato_holder() -> GameState {
+        let mut game = game();
+        game.phase = GamePhase::Playing;
+        game.current_holder = Some(STREAM_ID.to_string());
+        game
+    }
+
+    #[test]
+    fn new_connection_should_only_accept_hello() {
+        let state = ConnectionState::Connected;
+        let game = game_with_potato_holder();
+        assert_eq!(
+            state.verdict(&Message::Hello, &game, STREAM_ID),
+            Verdict::Accept
+        );
+        assert_eq!(
+            state.verdict(&subscribe(), &game, STREAM_ID),
+            Verdict::Disconnect
+        );
+        assert_eq!(
+            state.verdict(&challenge_result(), &game, STREAM_ID),
+            Verdict::Disconnect
+        );
+    }
+
+    #[test]
+    fn welcomed_connection_should_accept_subscribe() {
+        let state = ConnectionState::Welcomed;
+        let game = game();
+        assert_eq!(
+            state.verdict(&subscribe(), &game, STREAM_ID),
+            Verdict::Accept
+        );
+        assert_eq!(
+            state.verdict(&Message::Hello, &game, STREAM_ID),
+            Verdict::Ignore
+        );
+        assert_eq!(
+            state.verdict(&challenge_result(), &game, STREAM_ID),
+            Verdict::Disconnect
+        );
+    }
+
+    #[test]
+    fn welcomed_connection_should_reject_server_messages() {
+        let state = ConnectionState::Welcomed;
+        let game = game();
+        assert_eq!(
+            state.verdict(&Message::Welcome(Welcome { version: 1 }), &game, STREAM_ID),
+            Verdict::Disconnect
+        );
+        assert_eq!(
+            state.verdict(
+                &Message::SubscribeResult(SubscribeResult::Ok),
+                &game,
+                STREAM_ID
+            ),
+            Verdict::Disconnect
+        );
+    }
+
+    #[test]
+    fn subscribe_should_be_rejected_once_the_game_started() {
+        let state = ConnectionState::Welcomed;
+        let mut game = game();
+        game.phase = GamePhase::Playing;
+        assert_eq!(
+            state.verdict(&subscribe(), &game, STREAM_ID),
+            Verdict::Disconnect
+        );
+    }
+
+    #[test]
+    fn subscribe_should_take_back_the_slot_of_a_lost_player() {
+        let state = ConnectionState::Welcomed;
+        let mut game = game();
+        game.config.reconnect_grace_period = Duration::from_secs(10);
+        game.create_player("free_potato".to_string(), STREAM_ID, loopback_stream());
+        game.phase = GamePhase::Playing;
+        assert_eq!(
+            state.verdict(&subscribe(), &game, "127.0.0.1:1337"),
+            Verdict::Disconnect
+        );
+        game.connection_closed(STREAM_ID);
+        assert_eq!(
+            state.verdict(&subscribe(), &game, "127.0.0.1:1337"),
+            Verdict::Accept
+        );
+    }
+
+    #[test]
+    fn player_should_only_answer_when_holding_the_potato() {
+        let state = ConnectionState::Subscribed;
+        let mut game = game_with_potato_holder();
+        assert_eq!(
+            state.verdict(&challenge_result(), &game, STREAM_ID),
+            Verdict::Accept
+        );
+        game.current_holder = Some("127.0.0.1:1337".to_string());
+        assert_eq!(
+            state.verdict(&challenge_result(), &game, STREAM_ID),
+            Verdict::Ignore
+        );
+    }
+
+    #[test]
+    fn player_should_not_be_disconnected_for_out_of_sequence_messages() {
+        let state = ConnectionState::Subscribed;
+        let game = game();
+        assert_eq!(
+            state.verdict(&Message::Hello, &game, STREAM_ID),
+            Verdict::Ignore
+        );
+        assert_eq!(
+            state.verdict(&subscribe(), &game, STREAM_ID),
+            Verdict::Ignore
+        );
+        assert_eq!(
+            state.verdict(&Message::StartGame, &game, STREAM_ID),
+            Verdict::Ignore
+        );
+    }
+
+    fn admin_login(token: &str) -> Message {
+        Message::AdminLogin(AdminLogin {
+            token: token.to_string(),
+        })
+    }
+
+    #[test]
+    fn admin_commands_should_be_accepted_after_login_only() {
+        let mut game = game();
+        game.config.admin_token = Some("s3cr3t".to_string());
+        for message in [Message::StartGame, Message::ListPlayers, Message::AbortGame] {
+            assert_eq!(
+                ConnectionState::Connected.verdict(&message, &game, STREAM_ID),
+                Verdict::Disconnect
+            );
+            assert_eq!(
+                ConnectionState::Admin.verdict(&message, &game, STREAM_ID),
+                Verdict::Accept
+            );
+        }
+        assert_eq!(
+            ConnectionState::Admin.verdict(&subscribe(), &game, STREAM_ID),
+            Verdict::Disconnect
+        );
+    }
+
+    #[test]
+    fn admin_login_should_need_the_server_token() {
+        let mut game = game();
+        assert_eq!(
+            ConnectionState::Connected.verdict(&admin_login(""), &game, STREAM_ID),
+            Verdict::Disconnect
+        );
+        game.config.admin_token = Some("s3cr3t".to_string());
+        assert_eq!(
+            ConnectionState::Connected.verdict(&admin_login("guess"), &game, STREAM_ID),
+            Verdict::Disconnect
+        );
+        assert_eq!(
+            ConnectionState::Connected.verdict(&admin_login("s3cr3t"), &game, STREAM_ID),
+            Verdict::Accept
+        );
+        assert_eq!(
+            ConnectionState::Welcomed.verdict(&admin_login("s3cr3t"), &game, STREAM_ID),
+            Verdict::Disconnect
+        );
+    }
+
+    #[test]
+    fn room_should_only_change_before_subscribe() {
+        let game = game();
+        let join_room = Message::JoinRoom(JoinRoom {
+            room: "tournament".to_string(),
+        });
+        for state in [
+            ConnectionState::Connected,
+            ConnectionState::Welcomed,
+            ConnectionState::Admin,
+        ] {
+            assert_eq!(state.verdict(&join_room, &game, STREAM_ID), Verdict::Accept);
+        }
+        assert_eq!(
+            ConnectionState::Subscribed.verdict(&join_room, &game, STREAM_ID),
+            Verdict::Ignore
+        );
+    }
+
+    #[test]
+    fn spectator_should_be_accepted_at_any_time_and_never_play() {
+        let game = game_with_potato_holder();
+        assert_eq!(
+            ConnectionState::Welcomed.verdict(&Message::Spectate, &game, STREAM_ID),
+            Verdict::Accept
+        );
+        assert_eq!(
+            ConnectionState::Connected.verdict(&Message::Spectate, &game, STREAM_ID),
+            Verdict::Disconnect
+        );
+        for message in [challenge_result(), subscribe(), Message::StartGame] {
+            assert_eq!(
+                ConnectionState::Spectator.verdict(&message, &game, STREAM_ID),
+                Verdict::Ignore
+            );
+        }
+    }
+}
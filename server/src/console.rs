@@ -0,0 +1,214 @@
+use crate::game::GameState;
+use crate::lobby::Lobby;
+use crate::tui::{SortKey, View};
+use shared::PublicLeaderBoard;
+use std::io::BufRead;
+use std::sync::{Arc, Mutex};
+
+/// Commands typed on the server standard input
+#[derive(Debug, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    /// Print the registered players
+    Players,
+    /// Seal registrations and start the game
+    Start,
+    /// Remove a player from the game
+    Kick(String),
+    /// Change the number of challenges played before the end of the game
+    Rounds(u32),
+    /// Stop sending challenges after the current one
+    Pause,
+    /// Send the challenge put on hold by `pause`
+    Resume,
+    /// End the game right away
+    End,
+    /// List the open rooms
+    Rooms,
+    /// Send the next commands to another open room
+    Room(String),
+    /// Order the leaderboard by score or by steps
+    Sort(SortKey),
+}
+
+pub const HELP: &str =
+    "Commands: players, start, kick <name>, rounds <n>, pause, resume, end, rooms, room <name>, sort score|steps";
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> Result<ConsoleCommand, String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("");
+        let argument = words.next();
+        if words.next().is_some() {
+            return Err(format!("Too many arguments for {}", command));
+        }
+        match (command, argument) {
+            ("players", None) => Ok(ConsoleCommand::Players),
+            ("start", None) => Ok(ConsoleCommand::Start),
+            ("kick", Some(name)) => Ok(ConsoleCommand::Kick(name.to_string())),
+            ("rounds", Some(rounds)) => match rounds.parse::<u32>() {
+                Ok(rounds) if rounds > 0 => Ok(ConsoleCommand::Rounds(rounds)),
+                _ => Err(format!("Invalid number of rounds: {}", rounds)),
+            },
+            ("pause", None) => Ok(ConsoleCommand::Pause),
+            ("resume", None) => Ok(ConsoleCommand::Resume),
+            ("end", None) => Ok(ConsoleCommand::End),
+            ("rooms", None) => Ok(ConsoleCommand::Rooms),
+            ("room", Some(room)) => Ok(ConsoleCommand::Room(room.to_string())),
+            ("sort", Some(key)) => match SortKey::parse(key) {
+                Some(key) => Ok(ConsoleCommand::Sort(key)),
+                None => Err(format!("Invalid sort key: {}", key)),
+            },
+            _ => Err(format!("Unknown command: {}", line.trim())),
+        }
+    }
+}
+
+/// Reads commands on the standard input until it is closed.
+///
+/// Game commands apply to the room selected in `view`, the default one at first.
+pub fn run(lobby: &Arc<Lobby>, view: &Mutex<View>) {
+    println!("{}", HELP);
+    for line in std::io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut view = view.lock().unwrap_or_else(|err| err.into_inner());
+        match ConsoleCommand::parse(&line) {
+            Ok(ConsoleCommand::Rooms) => {
+                for (name, room) in lobby.open_rooms() {
+                    let game = room.lock();
+                    println!(
+                        "{}{}: {:?}, {} players",
+                        if name == view.room { "* " } else { "  " },
+                        name,
+                        game.phase,
+                        game.public_players.len()
+                    );
+                }
+            }
+            Ok(ConsoleCommand::Room(name)) => match lobby.get(&name) {
+                Some(_) => {
+                    println!("Commands now apply to room {}", name);
+                    view.room = name;
+                }
+                None => println!("Room {} is not open", name),
+            },
+            Ok(ConsoleCommand::Sort(key)) => {
+                view.sort = key;
+            }
+            Ok(command) => match lobby.get(&view.room) {
+                Some(game) => {
+                    execute(command, &mut game.lock(), view.sort);
+                    game.notify();
+                }
+                None => println!("Room {} is closed", view.room),
+            },
+            Err(err) => println!("{}\n{}", err, HELP),
+        }
+    }
+}
+
+fn execute(command: ConsoleCommand, game: &mut GameState, sort: SortKey) {
+    match command {
+        ConsoleCommand::Players => {
+            print!("{}", PublicLeaderBoard(sort.sorted(&game.public_players)));
+        }
+        ConsoleCommand::Start => {
+            game.start_game();
+        }
+        ConsoleCommand::Kick(name) => {
+            if game.kick(&name) {
+                println!("{} kicked", name);
+            } else {
+                println!("No player named {}", name);
+            }
+        }
+        ConsoleCommand::Rounds(rounds) => {
+            game.config.rounds = rounds;
+            println!(
+                "Game will end after {} challenges ({} played)",
+                rounds, game.nb_played_challenges
+            );
+        }
+        ConsoleCommand::Pause => {
+            game.pause();
+            println!("Game paused");
+        }
+        ConsoleCommand::Resume => {
+            game.resume();
+            println!("Game resumed");
+        }
+        ConsoleCommand::End => {
+            println!(" ==== Game ended from the console ==== ");
+            game.end_game();
+        }
+        ConsoleCommand::Rooms | ConsoleCommand::Room(_) | ConsoleCommand::Sort(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod console_tests {
+    use crate::console::ConsoleCommand;
+    use crate::tui::SortKey;
+
+    #[test]
+    fn commands_without_argument_should_be_parsed() {
+        assert_eq!(
+            ConsoleCommand::parse("players"),
+            Ok(ConsoleCommand::Players)
+        );
+        assert_eq!(ConsoleCommand::parse(" start "), Ok(ConsoleCommand::Start));
+        assert_eq!(ConsoleCommand::parse("pause"), Ok(ConsoleCommand::Pause));
+        assert_eq!(ConsoleCommand::parse("resume"), Ok(ConsoleCommand::Resume));
+        assert_eq!(ConsoleCommand::parse("end"), Ok(ConsoleCommand::End));
+        assert_eq!(ConsoleCommand::parse("rooms"), Ok(ConsoleCommand::Rooms));
+    }
+
+    #[test]
+    fn kick_should_take_a_name() {
+        assert_eq!(
+            ConsoleCommand::parse("kick dark_salad"),
+            Ok(ConsoleCommand::Kick("dark_salad".to_string()))
+        );
+        assert!(ConsoleCommand::parse("kick").is_err());
+    }
+
+    #[test]
+    fn room_should_take_a_name() {
+        assert_eq!(
+            ConsoleCommand::parse("room tournament"),
+            Ok(ConsoleCommand::Room("tournament".to_string()))
+        );
+        assert!(ConsoleCommand::parse("room").is_err());
+    }
+
+    #[test]
+    fn sort_should_take_score_or_steps() {
+        assert_eq!(
+            ConsoleCommand::parse("sort steps"),
+            Ok(ConsoleCommand::Sort(SortKey::Steps))
+        );
+        assert!(ConsoleCommand::parse("sort name").is_err());
+        assert!(ConsoleCommand::parse("sort").is_err());
+    }
+
+    #[test]
+    fn rounds_should_take_a_positive_number() {
+        assert_eq!(
+            ConsoleCommand::parse("rounds 10"),
+            Ok(ConsoleCommand::Rounds(10))
+        );
+        assert!(ConsoleCommand::parse("rounds 0").is_err());
+        assert!(ConsoleCommand::parse("rounds ten").is_err());
+    }
+
+    #[test]
+    fn unknown_commands_and_extra_arguments_should_be_rejected() {
+        assert!(ConsoleCommand::parse("restart").is_err());
+        assert!(ConsoleCommand::parse("start now").is_err());
+    }
+}
@@ -0,0 +1,1107 @@
+use crate::name_policy::NamePolicy;
+use rand::Rng;
+use shared::challenges::hash_cash::MD5HashCash as MD5HashCashChallenge;
+use shared::challenges::monstrous_maze::MonstrousMaze as MonstrousMazeChallenge;
+use shared::challenges::{
+    Challenge, Challenges, Challenges::MD5HashCash as MD5HashCashChallengeEnum,
+    Challenges::MonstrousMaze as MonstrousMazeChallengeEnum,
+};
+use shared::transcript::{Direction, Transcript};
+use shared::{Challenge::MD5HashCash, Challenge::MonstrousMaze};
+use shared::{
+    ChallengeAnswer, ChallengeResult, ChallengeValue, EndOfGame, MD5HashCashInput, Message,
+    MonstrousMazeInput, PublicLeaderBoard, PublicPlayer, ReportedChallengeResult, RoundSummary,
+    SubscribeError, SubscribeResult,
+};
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::{Shutdown, TcpStream};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+pub struct PublicPlayerTCPStream {
+    pub player: PublicPlayer,
+    pub stream: TcpStream,
+}
+
+/// Read-only connection that receives the broadcasts of the game
+pub struct SpectatorTCPStream {
+    pub stream_id: String,
+    pub stream: TcpStream,
+}
+
+/// Minimum number of registered players for `StartGame` to launch the game
+pub const MIN_PLAYERS: usize = 2;
+/// Time to answer a challenge, unless the server is run with `--challenge-timeout`
+pub const DEFAULT_CHALLENGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamePhase {
+    /// Registrations are open, waiting for `StartGame`
+    Joining,
+    /// Registrations are sealed and challenges are being played
+    Playing,
+    /// `EndOfGame` was sent
+    TheEnd,
+}
+
+/// Settings of a game, from the command line or the console
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    pub name_policy: NamePolicy,
+    /// Number of challenges played before `EndOfGame`
+    pub rounds: u32,
+    /// Time the potato holder has to answer before the round ends with `Timeout`
+    pub challenge_timeout: Duration,
+    /// Time a player whose connection was lost during the game has to subscribe again
+    /// under the same name before being `Unreachable`; zero to make it `Unreachable` at once
+    pub reconnect_grace_period: Duration,
+    /// Where every message sent and received is recorded, shared by all rooms
+    pub transcript: Option<Arc<Transcript>>,
+    /// Token expected in `AdminLogin`; without one, controllers are always refused
+    pub admin_token: Option<String>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            name_policy: NamePolicy::default(),
+            rounds: 3,
+            challenge_timeout: DEFAULT_CHALLENGE_TIMEOUT,
+            reconnect_grace_period: Duration::ZERO,
+            transcript: None,
+            admin_token: None,
+        }
+    }
+}
+
+/// Everything the server knows about the game, shared by all client threads.
+pub struct GameState {
+    pub config: GameConfig,
+    pub phase: GamePhase,
+    /// No challenge is sent while the game is paused
+    pub paused: bool,
+    /// `stream_id` of the player to send the next challenge to once the game is resumed
+    pub pending_challenge: Option<String>,
+    pub public_players_tcp_stream: Vec<PublicPlayerTCPStream>,
+    pub public_players: Vec<PublicPlayer>,
+    /// Never counted as players and never sent a challenge
+    pub spectators: Vec<SpectatorTCPStream>,
+    pub nb_played_challenges: i32,
+    pub current_challenge: Challenges,
+    /// `stream_id` of the player the current challenge was sent to
+    pub current_holder: Option<String>,
+    /// When the current challenge was sent, to measure the `used_time` of its answer
+    pub challenge_started_at: Option<Instant>,
+    /// When each player still in its reconnection grace period lost its connection,
+    /// by `stream_id`
+    pub disconnected_players: HashMap<String, Instant>,
+    /// Every round summary broadcast since the start of the game
+    pub round_summaries: Vec<RoundSummary>,
+}
+
+impl GameState {
+    pub fn new(config: GameConfig) -> GameState {
+        GameState {
+            config,
+            phase: GamePhase::Joining,
+            paused: false,
+            pending_challenge: None,
+            public_players_tcp_stream: Vec::new(),
+            public_players: Vec::new(),
+            spectators: Vec::new(),
+            nb_played_challenges: 0,
+            current_challenge: Challenges::None(),
+            current_holder: None,
+            challenge_started_at: None,
+            disconnected_players: HashMap::new(),
+            round_summaries: Vec::new(),
+        }
+    }
+
+    /// Seals registrations and sends the first challenge to a random player.
+    ///
+    /// Returns `false` when the game is already running or there are not enough players.
+    pub fn start_game(&mut self) -> bool {
+        if self.phase != GamePhase::Joining {
+            println!("The game has already started");
+            return false;
+        }
+        if self.public_players.len() < MIN_PLAYERS {
+            println!(
+                "Not enough players to start: {}/{}",
+                self.public_players.len(),
+                MIN_PLAYERS
+            );
+            return false;
+        }
+        println!(" ==== Starting game ==== ");
+        self.phase = GamePhase::Playing;
+        self.send_to_all_players(Message::PublicLeaderBoard(PublicLeaderBoard(
+            self.public_players.clone(),
+        )));
+        match get_random_next_player(self.public_players.clone()) {
+            Some(random_player) => self.pass_potato(random_player.stream_id),
+            None => {
+                println!("No more (active) players");
+                self.end_game();
+            }
+        }
+        true
+    }
+
+    /// Counts the round that just ended, then either ends the game or passes the potato
+    /// to `next_stream_id` (later, if the game is paused).
+    pub fn play_next_challenge(&mut self, next_stream_id: String) {
+        self.nb_played_challenges += 1;
+        if self.nb_played_challenges >= self.config.rounds as i32 {
+            self.end_game();
+            return;
+        }
+        self.pass_potato(next_stream_id);
+    }
+
+    /// Sends a challenge to the player `stream_id`, or keeps it on hold while paused.
+    fn pass_potato(&mut self, stream_id: String) {
+        if self.paused {
+            println!("Game paused, next challenge on hold");
+            self.pending_challenge = Some(stream_id);
+        } else {
+            self.launch_challenge(&stream_id);
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Sends the challenge put on hold while the game was paused.
+    pub fn resume(&mut self) {
+        self.paused = false;
+        if let Some(stream_id) = self.pending_challenge.take() {
+            self.launch_challenge(&stream_id);
+        }
+    }
+
+    /// Removes `name` from the game.
+    ///
+    /// Before the game starts the player is unregistered; afterwards it stays on the
+    /// leaderboard as inactive. The connection is closed in both cases and, if the
+    /// player held the potato, it goes to another random active player.
+    pub fn kick(&mut self, name: &str) -> bool {
+        let stream_id = match self
+            .public_players
+            .iter()
+            .find(|player| player.name == name)
+        {
+            Some(player) => player.stream_id.clone(),
+            None => return false,
+        };
+        if let Some(stream) = self.find_player_stream(&stream_id) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        self.connection_closed(&stream_id);
+        // A kicked player cannot come back
+        if self.disconnected_players.remove(&stream_id).is_some() {
+            self.set_player_unreachable(&stream_id);
+        }
+        true
+    }
+
+    /// Takes the player `stream_id` out of the game after its connection was closed or lost.
+    ///
+    /// Before the game starts the registration is dropped; during the game the player
+    /// becomes Unreachable, once its reconnection grace period is over if there is one;
+    /// after `EndOfGame` only the connection is forgotten.
+    pub fn connection_closed(&mut self, stream_id: &str) {
+        self.spectators
+            .retain(|spectator| spectator.stream_id != stream_id);
+        self.public_players_tcp_stream
+            .retain(|player_stream| player_stream.player.stream_id != stream_id);
+        match self.phase {
+            GamePhase::Joining => self
+                .public_players
+                .retain(|player| player.stream_id != stream_id),
+            GamePhase::Playing if !self.config.reconnect_grace_period.is_zero() => {
+                let active = self
+                    .public_players
+                    .iter()
+                    .any(|player| player.stream_id == stream_id && player.is_active);
+                if active && !self.disconnected_players.contains_key(stream_id) {
+                    println!(
+                        "{} may reconnect within {:?}",
+                        stream_id, self.config.reconnect_grace_period
+                    );
+                    self.disconnected_players
+                        .insert(stream_id.to_string(), Instant::now());
+                }
+            }
+            GamePhase::Playing => self.set_player_unreachable(stream_id),
+            GamePhase::TheEnd => {}
+        }
+    }
+
+    /// Whether `name` is a player who lost its connection and may take its slot back
+    pub fn can_reclaim(&self, name: &str) -> bool {
+        self.phase == GamePhase::Playing && self.disconnected_player(name).is_some()
+    }
+
+    fn disconnected_player(&self, name: &str) -> Option<String> {
+        self.public_players
+            .iter()
+            .find(|player| {
+                self.config.name_policy.same_name(&player.name, name)
+                    && self.disconnected_players.contains_key(&player.stream_id)
+            })
+            .map(|player| player.stream_id.clone())
+    }
+
+    /// Gives the slot of the disconnected player `name` to the connection `stream_id`,
+    /// with its score, its steps and the potato if it held it.
+    pub fn reclaim_player(&mut self, name: &str, stream_id: &str, stream: TcpStream) -> Message {
+        let old_stream_id = match self.disconnected_player(name) {
+            Some(old_stream_id) => old_stream_id,
+            None => {
+                return Message::SubscribeResult(SubscribeResult::Err(
+                    SubscribeError::AlreadyRegistered,
+                ))
+            }
+        };
+        self.disconnected_players.remove(&old_stream_id);
+        for player in self.public_players.iter_mut() {
+            if player.stream_id == old_stream_id {
+                player.stream_id = stream_id.to_string();
+            }
+        }
+        for holder in [&mut self.current_holder, &mut self.pending_challenge] {
+            if holder.as_deref() == Some(old_stream_id.as_str()) {
+                *holder = Some(stream_id.to_string());
+            }
+        }
+        if let Some(player) = self.get_current_player(stream_id.to_string()) {
+            println!("{} is back from {}", player.name, stream_id);
+            self.public_players_tcp_stream
+                .push(PublicPlayerTCPStream { player, stream });
+        }
+        Message::SubscribeResult(SubscribeResult::Ok)
+    }
+
+    /// Sends the leader board to a player who took its slot back, and the challenge in
+    /// progress again if it holds the potato; the time to answer keeps running.
+    pub fn catch_up(&mut self, stream_id: &str) {
+        let stream = match self.find_player_stream(stream_id) {
+            Some(stream) => stream,
+            None => return,
+        };
+        let mut sent = self.send(
+            &stream,
+            Message::PublicLeaderBoard(PublicLeaderBoard(self.public_players.clone())),
+        );
+        if sent.is_ok() && self.is_potato_holder(stream_id) {
+            sent = launch_game(
+                &self.current_challenge,
+                &stream,
+                self.config.transcript.as_deref(),
+                self.round(),
+            );
+        }
+        if let Err(err) = sent {
+            println!("Cannot catch {} up: {}", stream_id, err);
+            self.connection_closed(stream_id);
+        }
+    }
+
+    /// When the first disconnected player runs out of time to come back, if there is one
+    pub fn reconnect_deadline(&self) -> Option<Instant> {
+        let lost_at = self.disconnected_players.values().min()?;
+        Some(*lost_at + self.config.reconnect_grace_period)
+    }
+
+    /// Makes `Unreachable` the disconnected players whose grace period is over.
+    pub fn reconnect_grace_expired(&mut self) {
+        let now = Instant::now();
+        let grace_period = self.config.reconnect_grace_period;
+        let expired: Vec<String> = self
+            .disconnected_players
+            .iter()
+            .filter(|(_, lost_at)| **lost_at + grace_period <= now)
+            .map(|(stream_id, _)| stream_id.clone())
+            .collect();
+        for stream_id in expired {
+            self.disconnected_players.remove(&stream_id);
+            println!("{} did not reconnect in time", stream_id);
+            self.set_player_unreachable(&stream_id);
+        }
+    }
+
+    /// Marks the player inactive. If it held the potato, the round ends with an
+    /// `Unreachable` report and the potato goes to another random active player.
+    fn set_player_unreachable(&mut self, stream_id: &str) {
+        let player = match self
+            .public_players
+            .iter_mut()
+            .find(|player| player.stream_id == stream_id)
+        {
+            Some(player) => player,
+            None => return,
+        };
+        player.is_active = false;
+        let name = player.name.clone();
+
+        if self.is_potato_holder(stream_id) {
+            println!("{} is unreachable while holding the potato", name);
+            self.current_holder = None;
+            self.send_round_summarize(
+                challenge_name(&self.current_challenge),
+                vec![ReportedChallengeResult {
+                    name,
+                    value: ChallengeValue::Unreachable,
+                }],
+            );
+            match get_random_next_player(self.public_players.clone()) {
+                Some(next_player) => self.play_next_challenge(next_player.stream_id),
+                None => {
+                    println!("No more (active) players");
+                    self.end_game();
+                }
+            }
+        } else if self.pending_challenge.as_deref() == Some(stream_id) {
+            self.pending_challenge = None;
+            match get_random_next_player(self.public_players.clone()) {
+                Some(next_player) => self.pass_potato(next_player.stream_id),
+                None => {
+                    println!("No more (active) players");
+                    self.end_game();
+                }
+            }
+        }
+    }
+
+    /// When the challenge in progress times out, if there is one
+    pub fn challenge_deadline(&self) -> Option<Instant> {
+        self.current_holder.as_ref()?;
+        Some(self.challenge_started_at? + self.config.challenge_timeout)
+    }
+
+    /// Ends the round of a potato holder who did not answer in time: it loses a point,
+    /// leaves the active players and the potato goes to another random active player.
+    pub fn challenge_timed_out(&mut self) {
+        let stream_id = match self.current_holder.take() {
+            Some(stream_id) => stream_id,
+            None => return,
+        };
+        self.challenge_started_at = None;
+        let player = match self
+            .public_players
+            .iter_mut()
+            .find(|player| player.stream_id == stream_id)
+        {
+            Some(player) => player,
+            None => return,
+        };
+        update_player_score(player, false);
+        increment_player_steps(player);
+        player.is_active = false;
+        let name = player.name.clone();
+        println!("{} did not answer in time", name);
+        self.send_round_summarize(
+            challenge_name(&self.current_challenge),
+            vec![ReportedChallengeResult {
+                name,
+                value: ChallengeValue::Timeout,
+            }],
+        );
+        match get_random_next_player(self.public_players.clone()) {
+            Some(next_player) => self.play_next_challenge(next_player.stream_id),
+            None => {
+                println!("No more (active) players");
+                self.end_game();
+            }
+        }
+    }
+
+    /// Sends `EndOfGame` to every player and closes the write half of their connection.
+    ///
+    /// Players then close their side; see [`SharedGame::wait_for_end_of_game`].
+    pub fn end_game(&mut self) {
+        self.phase = GamePhase::TheEnd;
+        self.current_holder = None;
+        self.pending_challenge = None;
+        for (stream_id, _) in self.disconnected_players.drain() {
+            if let Some(player) = self
+                .public_players
+                .iter_mut()
+                .find(|player| player.stream_id == stream_id)
+            {
+                player.is_active = false;
+            }
+        }
+        self.send_to_all_players(Message::EndOfGame(EndOfGame {
+            leader_board: PublicLeaderBoard(self.public_players.clone()),
+        }));
+        for player_stream in self.public_players_tcp_stream.iter_mut() {
+            let _ = player_stream.stream.flush();
+            let _ = player_stream.stream.shutdown(Shutdown::Write);
+        }
+        for spectator in self.spectators.iter_mut() {
+            let _ = spectator.stream.flush();
+            let _ = spectator.stream.shutdown(Shutdown::Write);
+        }
+        println!(" ==== Game Over ==== ");
+    }
+
+    /// Closes the connections still open and reopens registrations with the same config.
+    pub fn reset(&mut self) {
+        for player_stream in self.public_players_tcp_stream.iter() {
+            let _ = player_stream.stream.shutdown(Shutdown::Both);
+        }
+        for spectator in self.spectators.iter() {
+            let _ = spectator.stream.shutdown(Shutdown::Both);
+        }
+        *self = GameState::new(self.config.clone());
+        println!(" ==== Waiting for players ==== ");
+    }
+
+    pub fn is_potato_holder(&self, stream_id: &str) -> bool {
+        self.current_holder.as_deref() == Some(stream_id)
+    }
+
+    /// Whether `token` opens a controller connection, never without an admin token
+    pub fn is_admin_token(&self, token: &str) -> bool {
+        self.config.admin_token.as_deref() == Some(token)
+    }
+
+    /// Round recorded in transcripts: `0` during registrations, then the round in progress
+    pub fn round(&self) -> u32 {
+        match self.phase {
+            GamePhase::Joining => 0,
+            _ => self.nb_played_challenges as u32 + 1,
+        }
+    }
+
+    /// Sends `message` to `stream`, recording it in the transcript if any.
+    pub fn send(&self, stream: &TcpStream, message: Message) -> std::io::Result<()> {
+        shared::write_recorded_message(
+            stream,
+            message,
+            self.config.transcript.as_deref(),
+            self.round(),
+        )
+    }
+
+    /// Records a message received from `stream_id` in the transcript if any.
+    pub fn record_received(&self, stream_id: &str, message: &Message) {
+        // The admin token never reaches the transcript
+        if let Message::AdminLogin(..) = message {
+            return;
+        }
+        if let Some(transcript) = &self.config.transcript {
+            if let Err(err) =
+                transcript.record(Direction::Received, stream_id, self.round(), message)
+            {
+                println!("Cannot write the transcript: {}", err);
+            }
+        }
+    }
+
+    /// Sends a random challenge to the player `stream_id`, who then holds the potato.
+    pub fn launch_challenge(&mut self, stream_id: &str) {
+        self.current_holder = Some(stream_id.to_string());
+        self.current_challenge = get_random_game();
+        self.challenge_started_at = Some(Instant::now());
+        let sent = match self.find_player_stream(stream_id) {
+            Some(stream) => launch_game(
+                &self.current_challenge,
+                &stream,
+                self.config.transcript.as_deref(),
+                self.round(),
+            ),
+            None => Err(std::io::ErrorKind::NotConnected.into()),
+        };
+        if let Err(err) = sent {
+            println!("Cannot send the challenge to {}: {}", stream_id, err);
+            self.connection_closed(stream_id);
+        }
+    }
+
+    pub fn get_current_player(&self, address: String) -> Option<PublicPlayer> {
+        let current_player = self
+            .public_players
+            .iter()
+            .find(|player| player.stream_id == address)?;
+        Some(current_player.clone())
+    }
+
+    pub fn create_player(&mut self, name: String, stream_id: &str, stream: TcpStream) -> Message {
+        let registered_names = self
+            .public_players
+            .iter()
+            .map(|player| player.name.as_str());
+        if let Err(err) = self.config.name_policy.check(&name, registered_names) {
+            return Message::SubscribeResult(SubscribeResult::Err(err));
+        }
+        let player = PublicPlayer {
+            name,
+            stream_id: stream_id.to_string(),
+            score: 0,
+            steps: 0,
+            is_active: true,
+            total_used_time: 0.0,
+        };
+        self.public_players.push(player.clone());
+        self.public_players_tcp_stream
+            .push(PublicPlayerTCPStream { player, stream });
+
+        Message::SubscribeResult(SubscribeResult::Ok)
+    }
+
+    fn replace_player_in_players(&mut self, player: &PublicPlayer) {
+        for public_player in self.public_players.iter_mut() {
+            if public_player.stream_id == player.stream_id {
+                *public_player = player.clone();
+            }
+        }
+    }
+
+    fn update_player_in_player_list(
+        &mut self,
+        success: bool,
+        addr: String,
+        used_time: f64,
+    ) -> Option<PublicPlayer> {
+        let current_player = self
+            .public_players
+            .iter()
+            .find(|player| player.stream_id == addr)?;
+        let mut current_player = current_player.clone();
+        update_player_score(&mut current_player, success);
+        increment_player_steps(&mut current_player);
+        current_player.total_used_time += used_time;
+        self.replace_player_in_players(&current_player);
+        Some(current_player)
+    }
+
+    /// Registers a spectator; it receives every broadcast from now on.
+    pub fn add_spectator(&mut self, stream_id: &str, stream: TcpStream) {
+        self.spectators.push(SpectatorTCPStream {
+            stream_id: stream_id.to_string(),
+            stream,
+        });
+    }
+
+    /// Sends `message` to every connected player and spectator; players who cannot be
+    /// reached are taken out of the game, spectators are forgotten.
+    pub fn send_to_all_players(&mut self, message: Message) {
+        let transcript = self.config.transcript.clone();
+        let round = self.round();
+        let send = |stream: &TcpStream| {
+            shared::write_recorded_message(stream, message.clone(), transcript.as_deref(), round)
+        };
+        self.spectators
+            .retain(|spectator| send(&spectator.stream).is_ok());
+        let unreachable_players: Vec<String> = self
+            .public_players_tcp_stream
+            .iter()
+            .filter(|player_stream| send(&player_stream.stream).is_err())
+            .map(|player_stream| player_stream.player.stream_id.clone())
+            .collect();
+        for stream_id in unreachable_players {
+            println!("Connection with {} lost", stream_id);
+            self.connection_closed(&stream_id);
+        }
+    }
+
+    fn send_round_summarize(
+        &mut self,
+        challenge_name: String,
+        reported_challenges: Vec<ReportedChallengeResult>,
+    ) {
+        let round_summary = RoundSummary {
+            challenge: challenge_name,
+            chain: reported_challenges,
+        };
+        self.round_summaries.push(round_summary.clone());
+        self.send_to_all_players(Message::RoundSummary(round_summary));
+    }
+
+    pub fn find_player_stream(&self, stream_id: &str) -> Option<TcpStream> {
+        let player_stream = self
+            .public_players_tcp_stream
+            .iter()
+            .find(|player_stream| player_stream.player.stream_id == stream_id)?;
+        player_stream.stream.try_clone().ok()
+    }
+
+    pub fn handle_client_challenge_response(
+        &mut self,
+        stream_id: &str,
+        current_player: PublicPlayer,
+        challenge_result: ChallengeResult,
+        mut reported_challenges: Vec<ReportedChallengeResult>,
+    ) -> Option<String> {
+        let current_challenge_used_time = self
+            .challenge_started_at
+            .take()
+            .map_or(0.0, |started_at| started_at.elapsed().as_secs_f64());
+        let challenge_name = challenge_name(&self.current_challenge);
+        // The answer to another challenge than the one sent is a bad result
+        let success = match (&challenge_result.answer, &self.current_challenge) {
+            (
+                ChallengeAnswer::MD5HashCash(hash_cash_answer),
+                MD5HashCashChallengeEnum(challenge),
+            ) => challenge.verify(hash_cash_answer),
+            (
+                ChallengeAnswer::MonstrousMaze(monstrous_maze_answer),
+                MonstrousMazeChallengeEnum(challenge),
+            ) => challenge.verify(monstrous_maze_answer),
+            _ => false,
+        };
+        reported_challenges.push(ReportedChallengeResult {
+            name: current_player.name.clone(),
+            value: generate_challenge_value(
+                success,
+                current_challenge_used_time,
+                challenge_result.next_target.clone(),
+            ),
+        });
+        match self.update_player_in_player_list(
+            success,
+            stream_id.to_string(),
+            current_challenge_used_time,
+        ) {
+            Some(_current_player) => {
+                match get_next_player(challenge_result, self.public_players.clone()) {
+                    Some(next_player) => {
+                        self.send_round_summarize(challenge_name, reported_challenges);
+                        Some(next_player.stream_id)
+                    }
+                    None => {
+                        println!("No more (active) players OR player not found");
+                        self.end_game();
+                        None
+                    }
+                }
+            }
+            None => {
+                // Kicked or timed out while its answer was on its way
+                println!("{} left the player list, its answer is dropped", stream_id);
+                None
+            }
+        }
+    }
+}
+
+/// Game state shared by the connection threads, the console and the main thread
+pub struct SharedGame {
+    state: Mutex<GameState>,
+    /// Notified after every change of the state
+    changed: Condvar,
+}
+
+impl SharedGame {
+    pub fn new(state: GameState) -> SharedGame {
+        SharedGame {
+            state: Mutex::new(state),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Locks the state, even if a connection thread panicked while holding it.
+    pub fn lock(&self) -> MutexGuard<'_, GameState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn notify(&self) {
+        self.changed.notify_all();
+    }
+
+    /// Blocks until `EndOfGame` was sent, ending the rounds of players who do not answer
+    /// in time and dropping players who do not reconnect in time meanwhile, then lets
+    /// players up to `grace_period` to close their connection.
+    pub fn wait_for_end_of_game(&self, grace_period: Duration) -> MutexGuard<'_, GameState> {
+        let mut game = self.lock();
+        while game.phase != GamePhase::TheEnd {
+            let deadline = [game.challenge_deadline(), game.reconnect_deadline()]
+                .into_iter()
+                .flatten()
+                .min();
+            game = match deadline {
+                Some(deadline) if deadline <= Instant::now() => {
+                    if game
+                        .challenge_deadline()
+                        .is_some_and(|deadline| deadline <= Instant::now())
+                    {
+                        game.challenge_timed_out();
+                    }
+                    game.reconnect_grace_expired();
+                    self.notify();
+                    game
+                }
+                Some(deadline) => {
+                    self.changed
+                        .wait_timeout(game, deadline - Instant::now())
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .0
+                }
+                None => self
+                    .changed
+                    .wait(game)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            };
+        }
+        let (game, timeout) = self
+            .changed
+            .wait_timeout_while(game, grace_period, |game| {
+                !game.public_players_tcp_stream.is_empty()
+            })
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if timeout.timed_out() {
+            println!(
+                "{} player(s) did not close their connection",
+                game.public_players_tcp_stream.len()
+            );
+        }
+        game
+    }
+}
+
+fn generate_challenge_value(success: bool, used_time: f64, next_target: String) -> ChallengeValue {
+    if success {
+        ChallengeValue::Ok {
+            used_time,
+            next_target,
+        }
+    } else {
+        ChallengeValue::BadResult {
+            used_time,
+            next_target,
+        }
+    }
+}
+
+fn update_player_score(player: &mut PublicPlayer, won: bool) {
+    player.score += if won { 0 } else { -1 };
+}
+
+fn increment_player_steps(player: &mut PublicPlayer) {
+    player.steps += 1;
+}
+
+/// Player designated by `next_target`, or a random active player when the target is
+/// empty, unknown or no longer active.
+fn get_next_player(
+    challenge_result: ChallengeResult,
+    public_players: Vec<PublicPlayer>,
+) -> Option<PublicPlayer> {
+    let next_player_result = public_players
+        .iter()
+        .find(|player| player.is_active && player.name == challenge_result.next_target);
+    match next_player_result {
+        Some(next_player) => Some(next_player.clone()),
+        None => get_random_next_player(public_players),
+    }
+}
+
+pub fn get_random_next_player(public_players: Vec<PublicPlayer>) -> Option<PublicPlayer> {
+    let mut rng = rand::thread_rng();
+    let active_players: Vec<&PublicPlayer> = public_players
+        .iter()
+        .filter(|player| player.is_active)
+        .collect();
+    if active_players.is_empty() {
+        return None;
+    }
+    let random_index: usize = rng.gen_range(0..active_players.len());
+    Some(active_players[random_index].clone())
+}
+
+pub fn get_random_game() -> Challenges {
+    let mut rng = rand::thread_rng();
+    let challenge_index: usize = rng.gen_range(0..=1);
+    match challenge_index {
+        0 => {
+            let challenge_input = MD5HashCashInput {
+                complexity: 9,
+                message: "hello".to_string(),
+            };
+            MD5HashCashChallengeEnum(MD5HashCashChallenge::new(challenge_input))
+        }
+        1 => {
+            let challenge_input = MonstrousMazeInput {
+                endurance: 10,
+                grid: "|I   X|".to_string(),
+            };
+            MonstrousMazeChallengeEnum(MonstrousMazeChallenge::new(challenge_input))
+        }
+        _ => panic!("Not implemented"),
+    }
+}
+
+pub fn launch_game(
+    challenge: &Challenges,
+    stream: &TcpStream,
+    transcript: Option<&Transcript>,
+    round: u32,
+) -> std::io::Result<()> {
+    match challenge {
+        Challenges::MD5HashCash(challenge) => shared::write_recorded_message(
+            stream,
+            Message::Challenge(MD5HashCash(challenge.input.clone())),
+            transcript,
+            round,
+        ),
+        Challenges::MonstrousMaze(challenge) => shared::write_recorded_message(
+            stream,
+            Message::Challenge(MonstrousMaze(challenge.input.clone())),
+            transcript,
+            round,
+        ),
+        Challenges::None() => Ok(()),
+    }
+}
+
+/// Name reported in `RoundSummary` for a challenge
+pub fn challenge_name(challenge: &Challenges) -> String {
+    match challenge {
+        Challenges::MD5HashCash(..) => MD5HashCashChallenge::name(),
+        Challenges::MonstrousMaze(..) => MonstrousMazeChallenge::name(),
+        Challenges::None() => "".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod game_tests {
+    use crate::game::{GameConfig, GamePhase, GameState};
+    use shared::challenges::monstrous_maze::MonstrousMaze;
+    use shared::challenges::{Challenge, Challenges};
+    use shared::{
+        ChallengeAnswer, ChallengeResult, MD5HashCashOutput, Message, MonstrousMazeInput,
+        PublicLeaderBoard, PublicPlayer,
+    };
+    use std::net::{TcpListener, TcpStream};
+    use std::time::{Duration, Instant};
+
+    fn player(name: &str, stream_id: &str) -> PublicPlayer {
+        PublicPlayer {
+            name: name.to_string(),
+            stream_id: stream_id.to_string(),
+            score: 0,
+            steps: 0,
+            is_active: true,
+            total_used_time: 0.0,
+        }
+    }
+
+    fn game_with_players(phase: GamePhase) -> GameState {
+        let mut game = GameState::new(GameConfig::default());
+        game.phase = phase;
+        game.public_players = vec![
+            player("free_potato", "127.0.0.1:1000"),
+            player("dark_salad", "127.0.0.1:2000"),
+            player("hot_potato", "127.0.0.1:3000"),
+        ];
+        game
+    }
+
+    #[test]
+    fn closed_connection_should_unregister_player_before_the_game() {
+        let mut game = game_with_players(GamePhase::Joining);
+        game.connection_closed("127.0.0.1:2000");
+        assert_eq!(game.public_players.len(), 2);
+        assert!(game
+            .public_players
+            .iter()
+            .all(|player| player.name != "dark_salad"));
+    }
+
+    #[test]
+    fn closed_connection_should_make_player_inactive_during_the_game() {
+        let mut game = game_with_players(GamePhase::Playing);
+        game.current_holder = Some("127.0.0.1:1000".to_string());
+        game.connection_closed("127.0.0.1:2000");
+        game.connection_closed("127.0.0.1:2000");
+        assert_eq!(game.public_players.len(), 3);
+        assert!(!game.public_players[1].is_active);
+        assert!(game.is_potato_holder("127.0.0.1:1000"));
+        assert_eq!(game.phase, GamePhase::Playing);
+    }
+
+    #[test]
+    fn lost_potato_holder_should_not_stop_the_game() {
+        let mut game = game_with_players(GamePhase::Playing);
+        game.current_holder = Some("127.0.0.1:1000".to_string());
+        game.connection_closed("127.0.0.1:1000");
+        // No player has a connection here, so the potato is lost by every player in turn
+        assert!(game.public_players.iter().all(|player| !player.is_active));
+        assert_eq!(game.phase, GamePhase::TheEnd);
+    }
+
+    #[test]
+    fn pending_challenge_should_be_reassigned_when_its_player_leaves() {
+        let mut game = game_with_players(GamePhase::Playing);
+        game.pause();
+        game.pending_challenge = Some("127.0.0.1:1000".to_string());
+        game.connection_closed("127.0.0.1:1000");
+        let pending = game.pending_challenge.clone().unwrap();
+        assert_ne!(pending, "127.0.0.1:1000");
+        assert_eq!(game.nb_played_challenges, 0);
+    }
+
+    #[test]
+    fn answer_should_update_score_steps_and_used_time() {
+        let mut game = game_with_players(GamePhase::Playing);
+        game.update_player_in_player_list(false, "127.0.0.1:1000".to_string(), 1.5);
+        game.update_player_in_player_list(true, "127.0.0.1:1000".to_string(), 0.5);
+        let player = &game.public_players[0];
+        assert_eq!(player.score, -1);
+        assert_eq!(player.steps, 2);
+        assert_eq!(player.total_used_time, 2.0);
+    }
+
+    #[test]
+    fn answer_to_another_challenge_should_be_a_bad_result() {
+        let mut game = game_with_players(GamePhase::Playing);
+        game.current_challenge =
+            Challenges::MonstrousMaze(MonstrousMaze::new(MonstrousMazeInput {
+                endurance: 10,
+                grid: "|I   X|".to_string(),
+            }));
+        let current_player = game.public_players[0].clone();
+        let next = game.handle_client_challenge_response(
+            "127.0.0.1:1000",
+            current_player,
+            ChallengeResult {
+                answer: ChallengeAnswer::MD5HashCash(MD5HashCashOutput {
+                    seed: 844,
+                    hashcode: "00441745D9BDF8E5D3C7872AC9DBB2C3".to_string(),
+                }),
+                next_target: "dark_salad".to_string(),
+            },
+            vec![],
+        );
+        assert_eq!(next.as_deref(), Some("127.0.0.1:2000"));
+        assert_eq!(game.public_players[0].score, -1);
+        assert_eq!(game.public_players[0].steps, 1);
+    }
+
+    #[test]
+    fn answer_of_a_player_no_longer_listed_should_be_dropped() {
+        let mut game = game_with_players(GamePhase::Playing);
+        let current_player = player("ghost_potato", "127.0.0.1:4000");
+        let next = game.handle_client_challenge_response(
+            "127.0.0.1:4000",
+            current_player,
+            ChallengeResult {
+                answer: ChallengeAnswer::MD5HashCash(MD5HashCashOutput {
+                    seed: 844,
+                    hashcode: "00441745D9BDF8E5D3C7872AC9DBB2C3".to_string(),
+                }),
+                next_target: "dark_salad".to_string(),
+            },
+            vec![],
+        );
+        assert_eq!(next, None);
+        assert!(game.public_players.iter().all(|player| player.steps == 0));
+        assert_eq!(game.phase, GamePhase::Playing);
+    }
+
+    #[test]
+    fn late_potato_holder_should_be_timed_out() {
+        let mut game = game_with_players(GamePhase::Playing);
+        game.current_holder = Some("127.0.0.1:1000".to_string());
+        game.challenge_started_at = Some(Instant::now() - Duration::from_secs(10));
+        assert!(game.challenge_deadline().unwrap() <= Instant::now());
+
+        game.challenge_timed_out();
+        let player = &game.public_players[0];
+        assert!(!player.is_active);
+        assert_eq!(player.score, -1);
+        assert_eq!(player.steps, 1);
+        assert!(game.nb_played_challenges >= 1);
+        assert!(!game.is_potato_holder("127.0.0.1:1000"));
+    }
+
+    fn game_with_grace_period() -> GameState {
+        let mut game = game_with_players(GamePhase::Playing);
+        game.config.reconnect_grace_period = Duration::from_secs(10);
+        game
+    }
+
+    #[test]
+    fn lost_player_should_take_its_slot_back_within_the_grace_period() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server_side, _) = listener.accept().unwrap();
+
+        let mut game = game_with_grace_period();
+        game.current_challenge =
+            Challenges::MonstrousMaze(MonstrousMaze::new(MonstrousMazeInput {
+                endurance: 10,
+                grid: "|I   X|".to_string(),
+            }));
+        game.current_holder = Some("127.0.0.1:1000".to_string());
+        game.connection_closed("127.0.0.1:1000");
+        assert!(game.public_players[0].is_active);
+        assert!(game.reconnect_deadline().is_some());
+        assert!(game.can_reclaim("Free_Potato"));
+        assert!(!game.can_reclaim("dark_salad"));
+
+        game.reclaim_player("free_potato", "127.0.0.1:1001", server_side);
+        game.catch_up("127.0.0.1:1001");
+        assert_eq!(game.public_players[0].stream_id, "127.0.0.1:1001");
+        assert!(game.is_potato_holder("127.0.0.1:1001"));
+        assert!(game.disconnected_players.is_empty());
+        assert!(matches!(
+            shared::receive_message(&client).unwrap(),
+            Message::PublicLeaderBoard(..)
+        ));
+        assert!(matches!(
+            shared::receive_message(&client).unwrap(),
+            Message::Challenge(..)
+        ));
+    }
+
+    #[test]
+    fn player_not_back_in_time_should_become_unreachable() {
+        let mut game = game_with_grace_period();
+        game.current_holder = Some("127.0.0.1:1000".to_string());
+        game.connection_closed("127.0.0.1:2000");
+        game.disconnected_players.insert(
+            "127.0.0.1:2000".to_string(),
+            Instant::now() - Duration::from_secs(11),
+        );
+        assert!(game.reconnect_deadline().unwrap() <= Instant::now());
+
+        game.reconnect_grace_expired();
+        assert!(!game.public_players[1].is_active);
+        assert!(!game.can_reclaim("dark_salad"));
+        assert!(game.is_potato_holder("127.0.0.1:1000"));
+
+        // A kicked player has no grace period
+        game.kick("hot_potato");
+        assert!(!game.public_players[2].is_active);
+        assert!(!game.can_reclaim("hot_potato"));
+    }
+
+    #[test]
+    fn spectators_should_receive_broadcasts_without_playing() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let spectator = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server_side, _) = listener.accept().unwrap();
+
+        let mut game = game_with_players(GamePhase::Joining);
+        game.public_players.truncate(1);
+        game.add_spectator("127.0.0.1:4000", server_side);
+        assert!(!game.start_game());
+
+        game.send_to_all_players(Message::PublicLeaderBoard(PublicLeaderBoard(
+            game.public_players.clone(),
+        )));
+        match shared::receive_message(&spectator).unwrap() {
+            Message::PublicLeaderBoard(leader_board) => assert_eq!(leader_board.0.len(), 1),
+            message => panic!("Unexpected message: {:?}", message),
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use crate::game::{GameConfig, GamePhase, GameState, SharedGame};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Room of the connections that never send `JoinRoom`
+pub const DEFAULT_ROOM: &str = "default";
+/// Maximum number of characters in a room name
+pub const MAX_ROOM_NAME_LENGTH: usize = 32;
+/// Time left to players to close their connection after `EndOfGame`
+pub const CLOSE_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RoomError {
+    /// Room names are made of 1 to 32 ASCII letters, digits, `-`, `_` or `.`
+    InvalidName,
+    /// The server already hosts its maximum number of rooms
+    TooManyRooms,
+}
+
+impl fmt::Display for RoomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoomError::InvalidName => write!(f, "invalid room name"),
+            RoomError::TooManyRooms => write!(f, "too many rooms"),
+        }
+    }
+}
+
+/// An open room and the number of connections in it
+struct OpenRoom {
+    game: Arc<SharedGame>,
+    connections: usize,
+}
+
+/// Game rooms hosted by the server, each with its own independent game.
+///
+/// The default room lives as long as the server. Other rooms are opened by the first
+/// `JoinRoom` naming them and closed once their game is over, or as soon as their last
+/// connection leaves before the game started: the next `JoinRoom` with the same name
+/// opens a fresh game.
+///
+/// Locks are always taken in the order game, then rooms.
+pub struct Lobby {
+    /// Config every new room starts with
+    config: GameConfig,
+    max_rooms: usize,
+    rooms: Mutex<HashMap<String, OpenRoom>>,
+}
+
+impl Lobby {
+    pub fn new(config: GameConfig, max_rooms: usize) -> Lobby {
+        let default_room = OpenRoom {
+            game: Arc::new(SharedGame::new(GameState::new(config.clone()))),
+            connections: 0,
+        };
+        Lobby {
+            config,
+            max_rooms,
+            rooms: Mutex::new(HashMap::from([(DEFAULT_ROOM.to_string(), default_room)])),
+        }
+    }
+
+    pub fn default_room(&self) -> Arc<SharedGame> {
+        self.get(DEFAULT_ROOM)
+            .expect("the default room is never removed")
+    }
+
+    /// The room `name` if it is open, for looking at it without opening it
+    pub fn get(&self, name: &str) -> Option<Arc<SharedGame>> {
+        self.rooms().get(name).map(|open| Arc::clone(&open.game))
+    }
+
+    /// Enters the room `name`, opening it if needed; the room is left when the returned
+    /// [`RoomMember`] is dropped.
+    pub fn join(self: &Arc<Self>, name: &str) -> Result<RoomMember, RoomError> {
+        if !is_valid_room_name(name) {
+            return Err(RoomError::InvalidName);
+        }
+        let mut rooms = self.rooms();
+        if let Some(open) = rooms.get_mut(name) {
+            open.connections += 1;
+            return Ok(self.member(name, &open.game));
+        }
+        if rooms.len() >= self.max_rooms {
+            return Err(RoomError::TooManyRooms);
+        }
+        let room = Arc::new(SharedGame::new(GameState::new(self.config.clone())));
+        rooms.insert(
+            name.to_string(),
+            OpenRoom {
+                game: Arc::clone(&room),
+                connections: 1,
+            },
+        );
+        println!(" ==== Room {} opened ==== ", name);
+
+        let lobby = Arc::clone(self);
+        let recycled_name = name.to_string();
+        let recycled_room = Arc::clone(&room);
+        thread::spawn(move || lobby.recycle_when_over(&recycled_name, &recycled_room));
+        Ok(self.member(name, &room))
+    }
+
+    /// Enters the default room, where every connection starts
+    pub fn join_default(self: &Arc<Self>) -> RoomMember {
+        self.join(DEFAULT_ROOM)
+            .expect("the default room is never removed")
+    }
+
+    /// Open rooms, in alphabetical order
+    pub fn open_rooms(&self) -> Vec<(String, Arc<SharedGame>)> {
+        let mut rooms: Vec<(String, Arc<SharedGame>)> = self
+            .rooms()
+            .iter()
+            .map(|(name, open)| (name.clone(), Arc::clone(&open.game)))
+            .collect();
+        rooms.sort_by(|(a, _), (b, _)| a.cmp(b));
+        rooms
+    }
+
+    fn member(self: &Arc<Self>, name: &str, game: &Arc<SharedGame>) -> RoomMember {
+        RoomMember {
+            lobby: Arc::clone(self),
+            name: name.to_string(),
+            game: Arc::clone(game),
+        }
+    }
+
+    /// Closes the room once its last connection left, if its game did not start:
+    /// a game in progress goes on without connections until it ends.
+    fn leave(&self, name: &str, room: &Arc<SharedGame>) {
+        let idle = match self.rooms().get_mut(name) {
+            Some(open) if Arc::ptr_eq(&open.game, room) => {
+                open.connections = open.connections.saturating_sub(1);
+                open.connections == 0 && name != DEFAULT_ROOM
+            }
+            _ => false,
+        };
+        if !idle {
+            return;
+        }
+        let mut game = room.lock();
+        if game.phase != GamePhase::Joining {
+            return;
+        }
+        let mut rooms = self.rooms();
+        if rooms
+            .get(name)
+            .is_some_and(|open| Arc::ptr_eq(&open.game, room) && open.connections == 0)
+        {
+            rooms.remove(name);
+            println!(" ==== Room {} closed ==== ", name);
+            // Lets its recycling thread end
+            game.phase = GamePhase::TheEnd;
+            room.notify();
+        }
+    }
+
+    fn recycle_when_over(&self, name: &str, room: &Arc<SharedGame>) {
+        let mut game = room.wait_for_end_of_game(CLOSE_GRACE_PERIOD);
+        let mut rooms = self.rooms();
+        if rooms
+            .get(name)
+            .is_some_and(|open| Arc::ptr_eq(&open.game, room))
+        {
+            rooms.remove(name);
+            drop(rooms);
+            game.reset();
+            println!(" ==== Room {} closed ==== ", name);
+        }
+    }
+
+    fn rooms(&self) -> std::sync::MutexGuard<'_, HashMap<String, OpenRoom>> {
+        self.rooms
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A connection in a room, counted to close the room once nobody is left in it
+pub struct RoomMember {
+    lobby: Arc<Lobby>,
+    name: String,
+    game: Arc<SharedGame>,
+}
+
+impl Deref for RoomMember {
+    type Target = SharedGame;
+
+    fn deref(&self) -> &SharedGame {
+        &self.game
+    }
+}
+
+impl Drop for RoomMember {
+    fn drop(&mut self) {
+        self.lobby.leave(&self.name, &self.game);
+    }
+}
+
+pub fn is_valid_room_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_ROOM_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+}
+
+#[cfg(test)]
+mod lobby_tests {
+    use crate::game::{GameConfig, GamePhase};
+    use crate::lobby::{is_valid_room_name, Lobby, RoomError, DEFAULT_ROOM};
+    use std::sync::Arc;
+
+    #[test]
+    fn room_names_should_be_short_and_ascii() {
+        assert!(is_valid_room_name("tournament-1"));
+        assert!(!is_valid_room_name(""));
+        assert!(!is_valid_room_name("salle à manger"));
+        assert!(!is_valid_room_name(&"a".repeat(33)));
+    }
+
+    #[test]
+    fn joining_a_room_twice_should_give_the_same_game() {
+        let lobby = Arc::new(Lobby::new(GameConfig::default(), 4));
+        let first = lobby.join("tournament").unwrap();
+        let second = lobby.join("tournament").unwrap();
+        assert!(std::ptr::eq(&*first, &*second));
+        assert!(std::ptr::eq(&*lobby.join_default(), &*lobby.default_room()));
+        let names: Vec<String> = lobby
+            .open_rooms()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["default", "tournament"]);
+    }
+
+    #[test]
+    fn rooms_should_run_independent_games() {
+        let lobby = Arc::new(Lobby::new(GameConfig::default(), 4));
+        let fast = lobby.join("fast").unwrap();
+        fast.lock().config.rounds = 1;
+        let slow = lobby.join("slow").unwrap();
+        assert_eq!(slow.lock().config.rounds, 3);
+        assert_eq!(lobby.default_room().lock().config.rounds, 3);
+    }
+
+    #[test]
+    fn lobby_should_refuse_rooms_beyond_its_capacity() {
+        let lobby = Arc::new(Lobby::new(GameConfig::default(), 2));
+        let _first = lobby.join("first").unwrap();
+        assert_eq!(lobby.join("second").err(), Some(RoomError::TooManyRooms));
+        assert_eq!(lobby.join("bad room").err(), Some(RoomError::InvalidName));
+    }
+
+    #[test]
+    fn room_should_close_when_its_last_connection_leaves() {
+        let lobby = Arc::new(Lobby::new(GameConfig::default(), 2));
+        let first = lobby.join("tournament").unwrap();
+        let second = lobby.join("tournament").unwrap();
+        drop(first);
+        assert!(lobby.get("tournament").is_some());
+        drop(second);
+        assert!(lobby.get("tournament").is_none());
+        // Its slot is free again, and the default room is never closed
+        drop(lobby.join("other").unwrap());
+        drop(lobby.join_default());
+        assert!(lobby.get(DEFAULT_ROOM).is_some());
+    }
+
+    #[test]
+    fn room_in_play_should_stay_open_without_connections() {
+        let lobby = Arc::new(Lobby::new(GameConfig::default(), 2));
+        let member = lobby.join("tournament").unwrap();
+        member.lock().phase = GamePhase::Playing;
+        drop(member);
+        assert!(lobby.get("tournament").is_some());
+    }
+
+    #[test]
+    fn looking_at_a_room_should_not_open_it() {
+        let lobby = Arc::new(Lobby::new(GameConfig::default(), 2));
+        assert!(lobby.get("tournament").is_none());
+        assert!(lobby.get("tournament").is_none());
+        assert_eq!(lobby.open_rooms().len(), 1);
+    }
+}
@@ -1,429 +1,385 @@
-use rand::Rng;
-use shared::challenges::hash_cash::MD5HashCash as MD5HashCashChallenge;
-use shared::challenges::monstrous_maze::MonstrousMaze as MonstrousMazeChallenge;
-use shared::challenges::{
-    Challenge, Challenges, Challenges::MD5HashCash as MD5HashCashChallengeEnum,
-    Challenges::MonstrousMaze as MonstrousMazeChallengeEnum,
-};
-use shared::MD5HashCashInput;
+#[cfg(test)]
+mod bots;
+mod connection;
+mod console;
+mod game;
+mod lobby;
+mod name_policy;
+mod tui;
+
+use clap::Parser;
+use connection::{ConnectionState, Verdict};
+use game::{GameConfig, GamePhase, GameState};
+use lobby::{Lobby, CLOSE_GRACE_PERIOD};
+use name_policy::NamePolicy;
+use shared::transcript::Transcript;
 use shared::Message;
-use shared::MonstrousMazeInput;
 use shared::PublicLeaderBoard;
 use shared::PublicPlayer;
-use shared::SubscribeError;
+use shared::ReportedChallengeResult;
 use shared::SubscribeResult;
 use shared::Welcome;
-use shared::{
-    BadResult, ChallengeAnswer, ChallengeResult, ChallengeValue, EndOfGame, Ok,
-    ReportedChallengeResult, RoundSummary,
-};
-use shared::{Challenge::MD5HashCash, Challenge::MonstrousMaze};
-use std::net::{TcpListener, TcpStream};
+use std::io::ErrorKind;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::{process, thread};
+use tui::{SortKey, View};
+
+/// Pause after a failed `accept`, so that a persistent error (e.g. too many open files)
+/// does not spin the listener thread
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
 
-struct PublicPlayerTCPStream {
-    player: PublicPlayer,
-    stream: TcpStream,
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Minimum number of characters in a player name
+    #[clap(long, required = false, default_value = "1", value_parser)]
+    min_name_length: usize,
+    /// Maximum number of characters in a player name
+    #[clap(long, required = false, default_value = "32", value_parser)]
+    max_name_length: usize,
+    /// Accept letters and digits outside of ASCII in player names
+    #[clap(long, value_parser)]
+    allow_unicode_names: bool,
+    /// Symbols accepted in player names besides letters and digits
+    #[clap(long, required = false, default_value = "-_.", value_parser)]
+    name_symbols: String,
+    /// Name that players cannot take (can be repeated), added to `admin` and `server`
+    /// unless --no-default-reserved-names is given
+    #[clap(long = "reserved-name", required = false, value_parser)]
+    reserved_names: Vec<String>,
+    /// Drop the default reserved names `admin` and `server`: only the --reserved-name
+    /// ones are left, none if there are none
+    #[clap(long, value_parser)]
+    no_default_reserved_names: bool,
+    /// Compare player names case-sensitively (`Potato` and `potato` are then distinct)
+    #[clap(long, value_parser)]
+    case_sensitive_names: bool,
+    /// Number of challenges played before the end of the game
+    #[clap(short = 'r', long, required = false, default_value = "3", value_parser)]
+    rounds: u32,
+    /// Seconds the potato holder has to answer before being timed out of the game
+    #[clap(long, required = false, default_value = "5", value_parser)]
+    challenge_timeout: u64,
+    /// Seconds a player who lost its connection during the game has to subscribe again
+    /// under the same name before being Unreachable
+    #[clap(long, required = false, default_value = "0", value_parser)]
+    reconnect_grace: u64,
+    /// Reopen registrations for a new game instead of exiting once a game is over
+    #[clap(long, value_parser)]
+    lobby_after_game: bool,
+    /// Maximum number of game rooms open at the same time, default room included
+    #[clap(long, required = false, default_value = "16", value_parser)]
+    max_rooms: usize,
+    /// JSON Lines file where every message sent and received is recorded
+    #[clap(long, value_parser)]
+    transcript: Option<PathBuf>,
+    /// Show the lobby, the potato holder, the leaderboard and the rounds on a live screen
+    #[clap(long, value_parser)]
+    tui: bool,
+    /// Order of the leaderboard on the screen and in the `players` command
+    #[clap(long, value_enum, default_value = "score")]
+    sort: SortKey,
+    /// Token patate-admin must send before `StartGame`, `ListPlayers` or `AbortGame`;
+    /// without it, the game is only controlled from the console
+    #[clap(long, value_parser)]
+    admin_token: Option<String>,
+}
+
+impl Args {
+    fn name_policy(&self) -> NamePolicy {
+        let mut name_policy = NamePolicy {
+            min_length: self.min_name_length,
+            max_length: self.max_name_length,
+            allow_unicode: self.allow_unicode_names,
+            allowed_symbols: self.name_symbols.clone(),
+            case_insensitive: !self.case_sensitive_names,
+            ..NamePolicy::default()
+        };
+        if self.no_default_reserved_names {
+            name_policy.reserved_names.clear();
+        }
+        name_policy
+            .reserved_names
+            .extend(self.reserved_names.iter().cloned());
+        name_policy
+    }
+
+    fn game_config(&self) -> std::io::Result<GameConfig> {
+        let transcript = match &self.transcript {
+            Some(path) => Some(Arc::new(Transcript::create(path)?)),
+            None => None,
+        };
+        Ok(GameConfig {
+            name_policy: self.name_policy(),
+            rounds: self.rounds,
+            challenge_timeout: Duration::from_secs(self.challenge_timeout),
+            reconnect_grace_period: Duration::from_secs(self.reconnect_grace),
+            transcript,
+            admin_token: self.admin_token.clone(),
+        })
+    }
 }
 
 fn main() {
+    let args = Args::parse();
     let listener = TcpListener::bind("127.0.0.1:7878");
     let listener = match listener {
         Ok(l) => l,
         Err(_err) => panic!("Cannot bind: {_err}"),
     };
-    static mut PUBLIC_PLAYERS_TCP_STREAM: Vec<PublicPlayerTCPStream> = Vec::new();
-    static mut PUBLIC_PLAYERS: Vec<PublicPlayer> = Vec::new();
-    static mut NB_PLAYED_CHALLENGES: i32 = 0;
-    static mut CURRENT_CHALLENGE: Challenges = Challenges::None();
-
-    for stream in listener.incoming() {
-        match stream {
-            Ok(stream) => unsafe {
-                println!("{}", stream.peer_addr().unwrap());
-                thread::spawn(move || loop {
-                    handle_client(&stream);
-                });
-            },
-            Err(_err) => {
-                panic!("Connection failed: {}", _err);
-            }
+    let config = match args.game_config() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Cannot create the transcript: {}", err);
+            process::exit(1);
         }
+    };
+    let lobby = Arc::new(Lobby::new(config, args.max_rooms));
+    let view = Arc::new(Mutex::new(View::new(args.sort)));
+    let console_lobby = Arc::clone(&lobby);
+    let console_view = Arc::clone(&view);
+    thread::spawn(move || console::run(&console_lobby, &console_view));
+    if args.tui {
+        let tui_lobby = Arc::clone(&lobby);
+        let tui_view = Arc::clone(&view);
+        thread::spawn(move || tui::run(&tui_lobby, &tui_view));
     }
+    let listener_lobby = Arc::clone(&lobby);
+    thread::spawn(move || accept_connections(listener, &listener_lobby));
 
-    unsafe fn handle_client(stream: &TcpStream) {
-        let response = shared::read_message(&stream);
-        let reported_challenges: Vec<ReportedChallengeResult> = vec![];
-        match serde_json::from_str(&response) {
-            Ok(response) => match response {
-                Message::Hello => {
-                    shared::send_message(&stream, Message::Welcome(Welcome { version: 1 }));
-                }
-                Message::Subscribe(subscribe) => {
-                    let public_player = create_player(
-                        subscribe.name,
-                        &mut PUBLIC_PLAYERS,
-                        stream.try_clone().unwrap(),
-                        &mut PUBLIC_PLAYERS_TCP_STREAM,
-                    );
-                    shared::send_message(&stream, public_player);
-                    if PUBLIC_PLAYERS.len() >= 2 {
-                        println!("{}", " ==== Starting game ==== ");
-                        send_to_all_players(Message::PublicLeaderBoard(PublicLeaderBoard(
-                            PUBLIC_PLAYERS.clone(),
-                        )));
-                        let random_player = get_random_next_player(PUBLIC_PLAYERS.clone());
-                        let random_player_stream = PUBLIC_PLAYERS_TCP_STREAM
-                            .iter()
-                            .find(|player| player.player.stream_id == random_player.stream_id);
-                        let random_player_stream =
-                            random_player_stream.unwrap().stream.try_clone().unwrap();
-                        CURRENT_CHALLENGE = launch_game(get_random_game(), random_player_stream);
-                    }
-                }
-                Message::ChallengeResult(challenge_result) => {
-                    let current_player_address: String;
-                    match stream.try_clone().unwrap().peer_addr() {
-                        Ok(address) => current_player_address = address.to_string(),
-                        Err(_err) => panic!("Failed to get peer address"),
-                    };
-                    let current_player: PublicPlayer;
-                    match get_current_player(current_player_address.clone()) {
-                        Some(player) => {
-                            current_player = player;
-                        }
-                        None => {
-                            panic!("Failed to get current player")
-                        }
-                    };
-
-                    match CURRENT_CHALLENGE {
-                        Challenges::None(..) => {
-                            panic!("Current challenge is None !");
-                        }
-                        _ => {}
-                    }
-                    let next_player_stream = handle_client_challenge_response(
-                        &stream,
-                        current_player,
-                        CURRENT_CHALLENGE.clone(),
-                        challenge_result.clone(),
-                        reported_challenges,
-                    );
-                    NB_PLAYED_CHALLENGES += 1;
-                    if NB_PLAYED_CHALLENGES >= 3 {
-                        println!("whats");
-                        end_game(true);
-                    }
-                    CURRENT_CHALLENGE = launch_game(get_random_game(), next_player_stream);
-                }
-                _ => {}
-            },
-            Err(err) => {
-                panic!("Failed to deserialize the message received: {}", err)
-            }
+    // The server lives as long as the game of its default room
+    let game = lobby.default_room();
+    loop {
+        let mut game = game.wait_for_end_of_game(CLOSE_GRACE_PERIOD);
+        if args.tui {
+            // The last changes may come faster than the refresh of the screen
+            tui::draw(&tui::render(&game, &View::new(args.sort), Instant::now()));
         }
-    }
-
-    unsafe fn end_game(exit: bool) {
-        send_to_all_players(Message::EndOfGame(EndOfGame {
-            leader_board: PublicLeaderBoard(PUBLIC_PLAYERS.clone()),
-        }));
-        println!("{}", " ==== Game Over ==== ");
-        if exit {
+        if !args.lobby_after_game {
             process::exit(0);
         }
+        game.reset();
     }
+}
 
-    unsafe fn get_current_player(address: String) -> Option<PublicPlayer> {
-        let current_player = PUBLIC_PLAYERS
-            .iter()
-            .find(|player| player.stream_id == address)?;
-        return Some(current_player.clone());
-    }
-    fn generate_challenge_value(
-        success: bool,
-        used_time: f64,
-        current_player_name: String,
-    ) -> ChallengeValue {
-        if success {
-            ChallengeValue::Ok {
-                0: Ok {
-                    used_time,
-                    next_target: current_player_name,
-                },
+/// Accepts connections until the server exits; a failed `accept` is logged and skipped.
+fn accept_connections(listener: TcpListener, lobby: &Arc<Lobby>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let lobby = Arc::clone(lobby);
+                thread::spawn(move || handle_connection(stream, &lobby));
             }
-        } else {
-            ChallengeValue::BadResult {
-                0: BadResult {
-                    used_time,
-                    next_target: current_player_name,
-                },
+            Err(err) => {
+                println!("Connection failed: {}", err);
+                thread::sleep(ACCEPT_RETRY_DELAY);
             }
         }
     }
+}
 
-    fn create_player(
-        name: String,
-        public_players: &mut Vec<PublicPlayer>,
-        stream: TcpStream,
-        public_players_stream: &mut Vec<PublicPlayerTCPStream>,
-    ) -> Message {
-        if !name.is_ascii() {
-            return Message::SubscribeResult(SubscribeResult::Err(SubscribeError::InvalidName));
+/// Reads messages from one peer until it breaks the protocol or closes the connection.
+///
+/// The connection starts in the default room and may move to another one with `JoinRoom`;
+/// it leaves its room when it ends.
+fn handle_connection(stream: TcpStream, lobby: &Arc<Lobby>) {
+    let stream_id = match stream.peer_addr() {
+        Ok(address) => address.to_string(),
+        Err(err) => {
+            println!("Dropping connection without peer address: {}", err);
+            return;
         }
-        for player in public_players.clone() {
-            if player.name.eq(&name) {
-                return Message::SubscribeResult(SubscribeResult::Err(
-                    SubscribeError::AlreadyRegistered,
-                ));
+    };
+    println!("{}", stream_id);
+    let mut state = ConnectionState::Connected;
+    let mut game = lobby.join_default();
+    loop {
+        let message = match shared::receive_message(&stream) {
+            Ok(message) => message,
+            Err(err) => {
+                if err.kind() == ErrorKind::UnexpectedEof {
+                    println!("{} closed the connection", stream_id);
+                } else {
+                    println!("Connection with {} lost: {}", stream_id, err);
+                }
+                game.lock().connection_closed(&stream_id);
+                game.notify();
+                return;
             }
-        }
-        let player = PublicPlayer {
-            name,
-            stream_id: stream.peer_addr().unwrap().to_string(),
-            score: 0,
-            steps: 0,
-            is_active: true,
-            total_used_time: 0.0,
         };
-        public_players.push(player.clone());
-        public_players_stream.push(PublicPlayerTCPStream { player, stream });
-
-        return Message::SubscribeResult(SubscribeResult::Ok);
-    }
-
-    fn update_player_score(player: &mut PublicPlayer, won: bool) {
-        player.score += if won { 0 } else { -1 };
-    }
-
-    fn increment_player_steps(player: &mut PublicPlayer) {
-        player.steps += 1;
-    }
-
-    unsafe fn replace_player_in_players(player: &PublicPlayer) {
-        for i in 0..PUBLIC_PLAYERS.len() {
-            if PUBLIC_PLAYERS[i].stream_id == player.stream_id {
-                PUBLIC_PLAYERS[i] = player.clone();
+        let mut locked_game = game.lock();
+        locked_game.record_received(&stream_id, &message);
+        match (state.verdict(&message, &locked_game, &stream_id), message) {
+            (Verdict::Accept, Message::JoinRoom(join_room)) => {
+                drop(locked_game);
+                match lobby.join(&join_room.room) {
+                    Ok(room) => {
+                        println!("{} joined room {}", stream_id, join_room.room);
+                        game = room;
+                    }
+                    Err(err) => {
+                        println!("Disconnecting {}: {} {}", stream_id, err, join_room.room);
+                        let _ = stream.shutdown(Shutdown::Both);
+                        return;
+                    }
+                }
+                continue;
+            }
+            (Verdict::Accept, message) => {
+                state = handle_client(&stream, &stream_id, &mut locked_game, state, message)
+            }
+            (Verdict::Ignore, _) => {
+                println!("Ignoring out of sequence message from {}", stream_id);
+            }
+            (Verdict::Disconnect, _) => {
+                println!("Disconnecting {}: message out of sequence", stream_id);
+                let _ = stream.shutdown(Shutdown::Both);
+                return;
             }
         }
+        drop(locked_game);
+        game.notify();
     }
+}
 
-    unsafe fn update_player_in_player_list(
-        success: bool,
-        addr: String,
-        used_time: f64,
-    ) -> Option<PublicPlayer> {
-        let current_player = PUBLIC_PLAYERS
-            .iter()
-            .find(|player| player.stream_id == addr)?;
-        let mut current_player = current_player.clone();
-        update_player_score(&mut current_player, success);
-        increment_player_steps(&mut current_player);
-        replace_player_in_players(&current_player);
-        current_player.total_used_time += used_time;
-        Some(current_player)
-    }
-
-    fn get_next_player(
-        challenge_result: ChallengeResult,
-        public_players: Vec<PublicPlayer>,
-    ) -> Option<PublicPlayer> {
-        if challenge_result.next_target == "" {
-            return Some(get_random_next_player(public_players));
+/// Handles a message accepted by the connection state machine and returns the new state.
+///
+/// Replies that cannot be written are dropped: the read loop notices the broken
+/// connection on its next message.
+fn handle_client(
+    stream: &TcpStream,
+    stream_id: &str,
+    game: &mut GameState,
+    state: ConnectionState,
+    message: Message,
+) -> ConnectionState {
+    let reported_challenges: Vec<ReportedChallengeResult> = vec![];
+    match message {
+        Message::Hello => {
+            let _ = game.send(stream, Message::Welcome(Welcome { version: 1 }));
+            ConnectionState::Welcomed
         }
-        let next_player_result = public_players
-            .iter()
-            .find(|player| player.name == challenge_result.next_target)?;
-        Some(next_player_result.clone())
-    }
-
-    fn get_random_next_player(public_players: Vec<PublicPlayer>) -> PublicPlayer {
-        let mut rng = rand::thread_rng();
-        let active_players: Vec<&PublicPlayer> = public_players
-            .iter()
-            .filter(|player| player.is_active)
-            .collect();
-        let random_index: usize = rng.gen_range(0..active_players.len());
-        return active_players[random_index].clone();
-    }
-
-    unsafe fn send_to_all_players(message: Message) {
-        PUBLIC_PLAYERS_TCP_STREAM
-            .iter_mut()
-            .for_each(|public_player_tcp_stream| {
-                shared::send_message(&public_player_tcp_stream.stream, message.clone());
-            });
-    }
-
-    unsafe fn send_round_summarize(
-        challenge_name: String,
-        reported_challenges: Vec<ReportedChallengeResult>,
-    ) {
-        send_to_all_players(Message::RoundSummary(RoundSummary {
-            challenge: challenge_name,
-            chain: reported_challenges.clone(),
-        }));
-    }
-
-    unsafe fn handle_client_challenge_response(
-        stream: &TcpStream,
-        current_player: PublicPlayer,
-        current_challenge: Challenges,
-        challenge_result: ChallengeResult,
-        mut reported_challenges: Vec<ReportedChallengeResult>,
-    ) -> TcpStream {
-        let challenge_timer = std::time::Instant::now();
-
-        let current_challenge_used_time = challenge_timer.elapsed().as_secs() as f64;
-        match challenge_result.clone().answer {
-            ChallengeAnswer::MD5HashCash {
-                0: hash_cash_answer,
-            } => {
-                let challenge_input;
-                match current_challenge {
-                    MD5HashCashChallengeEnum(challenge) => challenge_input = challenge.input,
-                    _ => {
-                        panic!("Error in handle_player_challenge client response")
-                    }
-                }
-                let current_challenge = MD5HashCashChallenge::new(challenge_input.clone());
-                let success = current_challenge.verify(&hash_cash_answer);
-                reported_challenges.push(ReportedChallengeResult {
-                    name: MD5HashCashChallenge::name(),
-                    value: generate_challenge_value(
-                        success,
-                        current_challenge_used_time,
-                        current_player.name.clone(),
-                    ),
-                });
-                match update_player_in_player_list(
-                    success,
-                    stream.peer_addr().unwrap().to_string(),
-                    current_challenge_used_time,
-                ) {
-                    Some(_current_player) => {
-                        match get_next_player(challenge_result.clone(), PUBLIC_PLAYERS.clone()) {
-                            Some(next_player) => {
-                                send_round_summarize(
-                                    MD5HashCashChallenge::name(),
-                                    reported_challenges.clone(),
-                                );
-                                let next_player_stream =
-                                    PUBLIC_PLAYERS_TCP_STREAM.iter().find(|player| {
-                                        player.player.stream_id == next_player.stream_id
-                                    });
-                                return next_player_stream.unwrap().stream.try_clone().unwrap();
-                            }
-                            None => {
-                                println!("No more (active) players OR player not found");
-                                end_game(false);
-                                process::exit(0);
-                            }
-                        }
-                    }
-                    None => {
-                        panic!("Failed to update current player in player list");
-                    }
+        Message::Subscribe(subscribe) => {
+            let player_stream = match stream.try_clone() {
+                Ok(player_stream) => player_stream,
+                Err(err) => {
+                    println!("Cannot register {}: {}", stream_id, err);
+                    return state;
                 }
+            };
+            // Once the game started, only a player who lost its connection may subscribe
+            let reclaimed = game.phase != GamePhase::Joining;
+            let subscribe_result = if reclaimed {
+                game.reclaim_player(&subscribe.name, stream_id, player_stream)
+            } else {
+                game.create_player(subscribe.name, stream_id, player_stream)
+            };
+            let subscribed = matches!(
+                subscribe_result,
+                Message::SubscribeResult(SubscribeResult::Ok)
+            );
+            let _ = game.send(stream, subscribe_result);
+            if subscribed && reclaimed {
+                game.catch_up(stream_id);
             }
-            ChallengeAnswer::MonstrousMaze {
-                0: monstrous_maze_answer,
-            } => {
-                let challenge_input;
-                match current_challenge {
-                    MonstrousMazeChallengeEnum(challenge) => challenge_input = challenge.input,
-                    _ => {
-                        panic!("Error in handle_player_challenge client response")
-                    }
-                }
-                let current_challenge = MonstrousMazeChallenge::new(challenge_input.clone());
-                let success = current_challenge.verify(&monstrous_maze_answer);
-                reported_challenges.push(ReportedChallengeResult {
-                    name: MonstrousMazeChallenge::name(),
-                    value: generate_challenge_value(
-                        success,
-                        current_challenge_used_time,
-                        current_player.name.clone(),
-                    ),
-                });
-                match update_player_in_player_list(
-                    success,
-                    stream.peer_addr().unwrap().to_string(),
-                    current_challenge_used_time,
-                ) {
-                    Some(_current_player) => {
-                        match get_next_player(challenge_result.clone(), PUBLIC_PLAYERS.clone()) {
-                            Some(next_player) => {
-                                send_round_summarize(
-                                    MonstrousMazeChallenge::name(),
-                                    reported_challenges.clone(),
-                                );
-                                let next_player_stream =
-                                    PUBLIC_PLAYERS_TCP_STREAM.iter().find(|player| {
-                                        player.player.stream_id == next_player.stream_id
-                                    });
-                                return next_player_stream.unwrap().stream.try_clone().unwrap();
-                            }
-                            None => {
-                                println!("No more (active) players OR player not found");
-                                end_game(false);
-                                process::exit(0);
-                            }
-                        }
-                    }
-                    None => {
-                        panic!("Failed to update current player in player list");
-                    }
+            if subscribed {
+                ConnectionState::Subscribed
+            } else {
+                state
+            }
+        }
+        Message::ChallengeResult(challenge_result) => {
+            let current_player: PublicPlayer = match game.get_current_player(stream_id.to_string())
+            {
+                Some(player) => player,
+                None => {
+                    println!("No player registered for {}", stream_id);
+                    return state;
                 }
+            };
+            game.current_holder = None;
+            if let Some(next_stream_id) = game.handle_client_challenge_response(
+                stream_id,
+                current_player,
+                challenge_result,
+                reported_challenges,
+            ) {
+                game.play_next_challenge(next_stream_id);
             }
+            state
+        }
+        Message::Spectate => {
+            let spectator_stream = match stream.try_clone() {
+                Ok(spectator_stream) => spectator_stream,
+                Err(err) => {
+                    println!("Cannot register spectator {}: {}", stream_id, err);
+                    return state;
+                }
+            };
+            game.add_spectator(stream_id, spectator_stream);
+            println!("{} is spectating", stream_id);
+            let _ = game.send(stream, Message::SubscribeResult(SubscribeResult::Ok));
+            let _ = game.send(
+                stream,
+                Message::PublicLeaderBoard(PublicLeaderBoard(game.public_players.clone())),
+            );
+            ConnectionState::Spectator
+        }
+        Message::AdminLogin(_) => {
+            println!("{} logged in as admin", stream_id);
+            let _ = game.send(stream, Message::SubscribeResult(SubscribeResult::Ok));
+            ConnectionState::Admin
+        }
+        Message::StartGame => {
+            // On a refusal, the players listed tell the admin why the game did not start
+            let reply = if game.start_game() {
+                Message::SubscribeResult(SubscribeResult::Ok)
+            } else {
+                Message::PublicLeaderBoard(PublicLeaderBoard(game.public_players.clone()))
+            };
+            let _ = game.send(stream, reply);
+            ConnectionState::Admin
         }
+        Message::ListPlayers => {
+            let _ = game.send(
+                stream,
+                Message::PublicLeaderBoard(PublicLeaderBoard(game.public_players.clone())),
+            );
+            ConnectionState::Admin
+        }
+        Message::AbortGame => {
+            println!(" ==== Game aborted ==== ");
+            game.end_game();
+            ConnectionState::Admin
+        }
+        _ => state,
     }
+}
 
-    fn get_random_game() -> Challenges {
-        let mut rng = rand::thread_rng();
-        let challenge_index: usize = rng.gen_range(0..=1);
-        match challenge_index {
-            0 => {
-                let challenge_input = MD5HashCashInput {
-                    complexity: 9,
-                    message: "hello".to_string(),
-                };
-                let challenge =
-                    MD5HashCashChallengeEnum(MD5HashCashChallenge::new(challenge_input.clone()));
-                return challenge;
-            }
-            1 => {
-                let challenge_input = MonstrousMazeInput {
-                    endurance: 10,
-                    grid: "|I   X|".to_string(),
-                };
-                let challenge = MonstrousMazeChallengeEnum(MonstrousMazeChallenge::new(
-                    challenge_input.clone(),
-                ));
-                return challenge;
-            }
-            _ => panic!("Not implemented"),
-        };
+#[cfg(test)]
+mod main_tests {
+    use crate::Args;
+    use clap::Parser;
+
+    fn reserved_names(args: &[&str]) -> Vec<String> {
+        let args = Args::parse_from([&["server"], args].concat());
+        args.name_policy().reserved_names
     }
 
-    fn launch_game(challenge: Challenges, stream: TcpStream) -> Challenges {
-        return match challenge {
-            Challenges::MD5HashCash(challenge) => {
-                shared::send_message(
-                    &stream,
-                    Message::Challenge(MD5HashCash(challenge.clone().input)),
-                );
-                Challenges::MD5HashCash(challenge)
-            }
-            Challenges::MonstrousMaze(challenge) => {
-                shared::send_message(
-                    &stream,
-                    Message::Challenge(MonstrousMaze(challenge.clone().input)),
-                );
-                Challenges::MonstrousMaze(challenge)
-            }
-            _ => Challenges::None(),
-        };
+    #[test]
+    fn reserved_names_should_be_added_to_or_replace_the_defaults() {
+        assert_eq!(reserved_names(&[]), vec!["admin", "server"]);
+        assert_eq!(
+            reserved_names(&["--reserved-name", "root"]),
+            vec!["admin", "server", "root"]
+        );
+        assert_eq!(
+            reserved_names(&["--no-default-reserved-names", "--reserved-name", "root"]),
+            vec!["root"]
+        );
+        assert!(reserved_names(&["--no-default-reserved-names"]).is_empty());
     }
 }
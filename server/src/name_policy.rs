@@ -0,0 +1,215 @@
+use shared::SubscribeError;
+
+/// Rules a name must follow to be accepted by `Subscribe`.
+#[derive(Debug, Clone)]
+pub struct NamePolicy {
+    /// Minimum number of characters
+    pub min_length: usize,
+    /// Maximum number of characters
+    pub max_length: usize,
+    /// Accept non-ASCII letters and digits
+    pub allow_unicode: bool,
+    /// Characters accepted besides letters and digits
+    pub allowed_symbols: String,
+    /// Names no player can take, whatever their case
+    pub reserved_names: Vec<String>,
+    /// Two names differing only by their case are the same player
+    pub case_insensitive: bool,
+}
+
+impl Default for NamePolicy {
+    fn default() -> Self {
+        NamePolicy {
+            min_length: 1,
+            max_length: 32,
+            allow_unicode: false,
+            allowed_symbols: "-_.".to_string(),
+            reserved_names: vec!["admin".to_string(), "server".to_string()],
+            case_insensitive: true,
+        }
+    }
+}
+
+impl NamePolicy {
+    /// Checks `name` against the policy and the names already registered.
+    ///
+    /// Malformed or reserved names give `InvalidName`, taken names give `AlreadyRegistered`.
+    pub fn check<'a>(
+        &self,
+        name: &str,
+        registered_names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), SubscribeError> {
+        let length = name.chars().count();
+        if length < self.min_length || length > self.max_length {
+            return Err(SubscribeError::InvalidName);
+        }
+        if !name.chars().all(|character| self.is_allowed(character)) {
+            return Err(SubscribeError::InvalidName);
+        }
+        if self
+            .reserved_names
+            .iter()
+            .any(|reserved| reserved.to_lowercase() == name.to_lowercase())
+        {
+            return Err(SubscribeError::InvalidName);
+        }
+        if registered_names
+            .into_iter()
+            .any(|registered| self.same_name(registered, name))
+        {
+            return Err(SubscribeError::AlreadyRegistered);
+        }
+        Ok(())
+    }
+
+    fn is_allowed(&self, character: char) -> bool {
+        if character.is_control() {
+            return false;
+        }
+        if character.is_ascii_alphanumeric() {
+            return true;
+        }
+        if character.is_alphanumeric() {
+            return self.allow_unicode;
+        }
+        self.allowed_symbols.contains(character)
+    }
+
+    /// Whether two names are the same player's
+    pub fn same_name(&self, first: &str, second: &str) -> bool {
+        if self.case_insensitive {
+            first.to_lowercase() == second.to_lowercase()
+        } else {
+            first == second
+        }
+    }
+}
+
+#[cfg(test)]
+mod name_policy_tests {
+    use crate::name_policy::NamePolicy;
+    use shared::SubscribeError;
+
+    fn check(policy: &NamePolicy, name: &str, registered: &[&str]) -> Result<(), SubscribeError> {
+        policy.check(name, registered.iter().copied())
+    }
+
+    #[test]
+    fn regular_name_should_be_accepted() {
+        let policy = NamePolicy::default();
+        assert!(check(&policy, "free_potato", &["dark_salad"]).is_ok());
+        assert!(check(&policy, "free_potato-2", &[]).is_ok());
+    }
+
+    #[test]
+    fn empty_name_should_be_invalid() {
+        let policy = NamePolicy::default();
+        assert!(matches!(
+            check(&policy, "", &[]),
+            Err(SubscribeError::InvalidName)
+        ));
+    }
+
+    #[test]
+    fn too_long_name_should_be_invalid() {
+        let policy = NamePolicy::default();
+        let name = "a".repeat(10 * 1024);
+        assert!(matches!(
+            check(&policy, &name, &[]),
+            Err(SubscribeError::InvalidName)
+        ));
+        assert!(check(&policy, &"a".repeat(policy.max_length), &[]).is_ok());
+    }
+
+    #[test]
+    fn too_short_name_should_be_invalid() {
+        let policy = NamePolicy {
+            min_length: 3,
+            ..NamePolicy::default()
+        };
+        assert!(matches!(
+            check(&policy, "ab", &[]),
+            Err(SubscribeError::InvalidName)
+        ));
+        assert!(check(&policy, "abc", &[]).is_ok());
+    }
+
+    #[test]
+    fn control_characters_should_be_invalid() {
+        let policy = NamePolicy {
+            allowed_symbols: "\n\t".to_string(),
+            ..NamePolicy::default()
+        };
+        assert!(matches!(
+            check(&policy, "free\npotato", &[]),
+            Err(SubscribeError::InvalidName)
+        ));
+        assert!(matches!(
+            check(&policy, "free\u{1b}[2Jpotato", &[]),
+            Err(SubscribeError::InvalidName)
+        ));
+    }
+
+    #[test]
+    fn symbols_outside_of_the_allowed_ones_should_be_invalid() {
+        let policy = NamePolicy::default();
+        assert!(matches!(
+            check(&policy, "free potato", &[]),
+            Err(SubscribeError::InvalidName)
+        ));
+        let policy = NamePolicy {
+            allowed_symbols: " ".to_string(),
+            ..NamePolicy::default()
+        };
+        assert!(check(&policy, "free potato", &[]).is_ok());
+    }
+
+    #[test]
+    fn unicode_letters_should_depend_on_the_policy() {
+        let policy = NamePolicy::default();
+        assert!(matches!(
+            check(&policy, "patate_brûlée", &[]),
+            Err(SubscribeError::InvalidName)
+        ));
+        let policy = NamePolicy {
+            allow_unicode: true,
+            ..NamePolicy::default()
+        };
+        assert!(check(&policy, "patate_brûlée", &[]).is_ok());
+    }
+
+    #[test]
+    fn reserved_names_should_be_invalid_whatever_their_case() {
+        let policy = NamePolicy::default();
+        assert!(matches!(
+            check(&policy, "Admin", &[]),
+            Err(SubscribeError::InvalidName)
+        ));
+        assert!(matches!(
+            check(&policy, "server", &[]),
+            Err(SubscribeError::InvalidName)
+        ));
+    }
+
+    #[test]
+    fn names_differing_only_by_case_should_be_already_registered() {
+        let policy = NamePolicy::default();
+        assert!(matches!(
+            check(&policy, "Free_Potato", &["free_potato"]),
+            Err(SubscribeError::AlreadyRegistered)
+        ));
+    }
+
+    #[test]
+    fn case_sensitive_policy_should_accept_names_differing_by_case() {
+        let policy = NamePolicy {
+            case_insensitive: false,
+            ..NamePolicy::default()
+        };
+        assert!(check(&policy, "Free_Potato", &["free_potato"]).is_ok());
+        assert!(matches!(
+            check(&policy, "free_potato", &["free_potato"]),
+            Err(SubscribeError::AlreadyRegistered)
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+use crate::game::{challenge_name, GamePhase, GameState};
+use crate::lobby::{Lobby, DEFAULT_ROOM};
+use shared::PublicPlayer;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time between two looks at the game state; the screen is only redrawn when it changed
+pub const REFRESH_PERIOD: Duration = Duration::from_millis(250);
+/// Round summaries shown at the bottom of the screen, the latest ones
+const ROUND_LOG_LENGTH: usize = 10;
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Orders of the leaderboard, chosen with `--sort` or the `sort` console command
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Highest score first, then most steps
+    Score,
+    /// Most steps first, then highest score
+    Steps,
+}
+
+impl SortKey {
+    pub fn parse(key: &str) -> Option<SortKey> {
+        match key {
+            "score" => Some(SortKey::Score),
+            "steps" => Some(SortKey::Steps),
+            _ => None,
+        }
+    }
+
+    /// Leaderboard in this order, ties broken by name
+    pub fn sorted(self, players: &[PublicPlayer]) -> Vec<PublicPlayer> {
+        let mut players = players.to_vec();
+        players.sort_by(|a, b| {
+            let order = match self {
+                SortKey::Score => (b.score, b.steps).cmp(&(a.score, a.steps)),
+                SortKey::Steps => (b.steps, b.score).cmp(&(a.steps, a.score)),
+            };
+            order.then_with(|| a.name.cmp(&b.name))
+        });
+        players
+    }
+}
+
+/// What the operator is looking at: the room the console commands apply to and the
+/// order of the leaderboard
+#[derive(Debug, Clone)]
+pub struct View {
+    pub room: String,
+    pub sort: SortKey,
+}
+
+impl View {
+    pub fn new(sort: SortKey) -> View {
+        View {
+            room: DEFAULT_ROOM.to_string(),
+            sort,
+        }
+    }
+}
+
+/// Redraws the selected room on the terminal whenever its state changes, forever.
+///
+/// Lines printed by the server and the console stay on screen until the next change.
+pub fn run(lobby: &Arc<Lobby>, view: &Mutex<View>) {
+    let mut last_screen = String::new();
+    loop {
+        let view = view.lock().unwrap_or_else(|err| err.into_inner()).clone();
+        let screen = match lobby.get(&view.room) {
+            Some(game) => render(&game.lock(), &view, Instant::now()),
+            None => format!("Room {} is not open\n", view.room),
+        };
+        if screen != last_screen {
+            draw(&screen);
+            last_screen = screen;
+        }
+        thread::sleep(REFRESH_PERIOD);
+    }
+}
+
+/// Replaces the terminal content with `screen`
+pub fn draw(screen: &str) {
+    print!("{}{}", CLEAR_SCREEN, screen);
+    let _ = std::io::stdout().flush();
+}
+
+/// Lobby, potato holder, leaderboard and latest round summaries of a room, with ANSI styles
+pub fn render(game: &GameState, view: &View, now: Instant) -> String {
+    let mut screen = String::new();
+    let _ = writeln!(
+        screen,
+        "{}La patate chaude{} - room {} - {}{}",
+        BOLD,
+        RESET,
+        view.room,
+        phase_label(game),
+        if game.paused { " (paused)" } else { "" }
+    );
+
+    title(
+        &mut screen,
+        &format!(
+            "Players ({} registered, {} spectators)",
+            game.public_players.len(),
+            game.spectators.len()
+        ),
+    );
+    for player in game.public_players.iter() {
+        let status = if game.disconnected_players.contains_key(&player.stream_id) {
+            "reconnecting"
+        } else if player.is_active {
+            "active"
+        } else {
+            "inactive"
+        };
+        let _ = writeln!(
+            screen,
+            "{}  {:<24} {:<22} {}{}",
+            player_style(game, player),
+            player.name,
+            player.stream_id,
+            status,
+            RESET
+        );
+    }
+
+    title(&mut screen, "Potato");
+    match potato_holder(game) {
+        Some(holder) => {
+            let elapsed = game
+                .challenge_started_at
+                .map_or(Duration::ZERO, |started_at| {
+                    now.saturating_duration_since(started_at)
+                });
+            let _ = writeln!(
+                screen,
+                "  {}{}{} holds {} for {}s / {}s",
+                YELLOW,
+                holder.name,
+                RESET,
+                challenge_name(&game.current_challenge),
+                elapsed.as_secs(),
+                game.config.challenge_timeout.as_secs()
+            );
+        }
+        None if game.paused && game.pending_challenge.is_some() => {
+            let _ = writeln!(screen, "  On hold until resume");
+        }
+        None => {
+            let _ = writeln!(screen, "  Nobody");
+        }
+    }
+
+    title(
+        &mut screen,
+        &format!(
+            "Leaderboard (by {})",
+            match view.sort {
+                SortKey::Score => "score",
+                SortKey::Steps => "steps",
+            }
+        ),
+    );
+    let _ = writeln!(
+        screen,
+        "  {:>3} {:<24} {:>6} {:>6} {:>10}",
+        "#", "name", "score", "steps", "used_time"
+    );
+    for (rank, player) in view.sort.sorted(&game.public_players).iter().enumerate() {
+        let _ = writeln!(
+            screen,
+            "{}  {:>3} {:<24} {:>6} {:>6} {:>10.3}{}",
+            player_style(game, player),
+            rank + 1,
+            player.name,
+            player.score,
+            player.steps,
+            player.total_used_time,
+            RESET
+        );
+    }
+
+    title(&mut screen, "Rounds");
+    let skipped = game.round_summaries.len().saturating_sub(ROUND_LOG_LENGTH);
+    for (index, round_summary) in game.round_summaries.iter().enumerate().skip(skipped) {
+        let _ = writeln!(screen, "  {:>3} {}", index + 1, round_summary);
+    }
+    screen
+}
+
+fn title(screen: &mut String, title: &str) {
+    let _ = writeln!(screen, "\n{}{}{}", BOLD, title, RESET);
+}
+
+fn phase_label(game: &GameState) -> String {
+    match game.phase {
+        GamePhase::Joining => "Joining".to_string(),
+        GamePhase::Playing => format!(
+            "Playing round {}/{}",
+            game.round().min(game.config.rounds),
+            game.config.rounds
+        ),
+        GamePhase::TheEnd => "TheEnd".to_string(),
+    }
+}
+
+fn potato_holder(game: &GameState) -> Option<&PublicPlayer> {
+    let holder = game.current_holder.as_ref()?;
+    game.public_players
+        .iter()
+        .find(|player| &player.stream_id == holder)
+}
+
+/// The potato holder in yellow, inactive players dimmed
+fn player_style(game: &GameState, player: &PublicPlayer) -> &'static str {
+    if game.is_potato_holder(&player.stream_id) {
+        YELLOW
+    } else if !player.is_active {
+        DIM
+    } else {
+        ""
+    }
+}
+
+#[cfg(test)]
+mod tui_tests {
+    use crate::game::{GameConfig, GamePhase, GameState};
+    use crate::tui::{render, SortKey, View};
+    use shared::challenges::hash_cash::MD5HashCash;
+    use shared::challenges::{Challenge, Challenges};
+    use shared::{MD5HashCashInput, PublicPlayer};
+    use std::time::{Duration, Instant};
+
+    fn player(name: &str, stream_id: &str, score: i32, steps: u32) -> PublicPlayer {
+        PublicPlayer {
+            name: name.to_string(),
+            stream_id: stream_id.to_string(),
+            score,
+            steps,
+            is_active: true,
+            total_used_time: 0.0,
+        }
+    }
+
+    fn names(players: &[PublicPlayer]) -> Vec<&str> {
+        players.iter().map(|player| player.name.as_str()).collect()
+    }
+
+    #[test]
+    fn leaderboard_should_be_sorted_by_the_chosen_key() {
+        let players = vec![
+            player("free_potato", "127.0.0.1:1000", -1, 3),
+            player("dark_salad", "127.0.0.1:2000", 0, 1),
+            player("hot_potato", "127.0.0.1:3000", 0, 2),
+        ];
+        assert_eq!(
+            names(&SortKey::Score.sorted(&players)),
+            vec!["hot_potato", "dark_salad", "free_potato"]
+        );
+        assert_eq!(
+            names(&SortKey::Steps.sorted(&players)),
+            vec!["free_potato", "hot_potato", "dark_salad"]
+        );
+        assert_eq!(SortKey::parse("steps"), Some(SortKey::Steps));
+        assert_eq!(SortKey::parse("name"), None);
+    }
+
+    #[test]
+    fn screen_should_show_the_potato_holder_and_elapsed_time() {
+        let mut game = GameState::new(GameConfig::default());
+        game.phase = GamePhase::Playing;
+        game.public_players = vec![
+            player("free_potato", "127.0.0.1:1000", 0, 0),
+            player("dark_salad", "127.0.0.1:2000", 0, 1),
+        ];
+        game.disconnected_players
+            .insert("127.0.0.1:1000".to_string(), Instant::now());
+        game.current_challenge = Challenges::MD5HashCash(MD5HashCash::new(MD5HashCashInput {
+            complexity: 9,
+            message: "hello".to_string(),
+        }));
+        game.current_holder = Some("127.0.0.1:2000".to_string());
+        let started_at = Instant::now();
+        game.challenge_started_at = Some(started_at);
+
+        let screen = render(
+            &game,
+            &View::new(SortKey::Steps),
+            started_at + Duration::from_secs(3),
+        );
+        assert!(screen.contains("Playing round 1/3"));
+        assert!(screen.contains("127.0.0.1:1000         reconnecting"));
+        assert!(screen.contains("dark_salad\x1b[0m holds MD5HashCash for 3s / 5s"));
+        assert!(screen.contains("Leaderboard (by steps)"));
+        let first = screen.find("  1 dark_salad").unwrap();
+        assert!(first < screen.find("  2 free_potato").unwrap());
+    }
+}
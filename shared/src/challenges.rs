@@ -1,5 +1,15 @@
+// The solvers are kept as their authors wrote them, older than these lints
+#[allow(clippy::bool_assert_comparison, clippy::unnecessary_cast)]
 pub mod hash_cash;
+pub mod maze_render;
+#[allow(
+    clippy::bool_assert_comparison,
+    clippy::len_zero,
+    clippy::ptr_arg,
+    clippy::unnecessary_cast
+)]
 pub mod monstrous_maze;
+use crate::ChallengeAnswer;
 use hash_cash::MD5HashCash;
 use monstrous_maze::MonstrousMaze;
 
@@ -25,6 +35,31 @@ pub trait Challenge {
     fn verify(&self, answer: &Self::Output) -> bool;
 }
 
+/// Answer of the shared solvers to a challenge as received on the wire
+pub fn solve_challenge(challenge: &crate::Challenge) -> ChallengeAnswer {
+    match challenge {
+        crate::Challenge::MD5HashCash(input) => {
+            ChallengeAnswer::MD5HashCash(MD5HashCash::new(input.clone()).solve())
+        }
+        crate::Challenge::MonstrousMaze(input) => {
+            ChallengeAnswer::MonstrousMaze(MonstrousMaze::new(input.clone()).solve())
+        }
+    }
+}
+
+/// Checks `answer` with the challenge it answers; an answer of another type is invalid.
+pub fn verify_answer(challenge: &crate::Challenge, answer: &ChallengeAnswer) -> bool {
+    match (challenge, answer) {
+        (crate::Challenge::MD5HashCash(input), ChallengeAnswer::MD5HashCash(output)) => {
+            MD5HashCash::new(input.clone()).verify(output)
+        }
+        (crate::Challenge::MonstrousMaze(input), ChallengeAnswer::MonstrousMaze(output)) => {
+            MonstrousMaze::new(input.clone()).verify(output)
+        }
+        _ => false,
+    }
+}
+
 pub fn convert_string_to_binary(input: String) -> String {
     let mut name_in_binary = "".to_string();
     // Call into_bytes() which returns a Vec<u8>, and iterate accordingly
@@ -68,3 +103,40 @@ pub fn check_number_of_zero(input: String, complexity: u32) -> bool {
     }
     false
 }
+
+#[cfg(test)]
+mod challenges_tests {
+    use crate::challenges::{solve_challenge, verify_answer};
+    use crate::{
+        Challenge, ChallengeAnswer, MD5HashCashInput, MonstrousMazeInput, MonstrousMazeOutput,
+    };
+
+    #[test]
+    fn solved_challenges_should_be_verified() {
+        let challenges = [
+            Challenge::MD5HashCash(MD5HashCashInput {
+                complexity: 9,
+                message: "hello".to_string(),
+            }),
+            Challenge::MonstrousMaze(MonstrousMazeInput {
+                endurance: 2,
+                grid: "|I M X|".to_string(),
+            }),
+        ];
+        for challenge in challenges.iter() {
+            assert!(verify_answer(challenge, &solve_challenge(challenge)));
+        }
+    }
+
+    #[test]
+    fn answer_to_another_challenge_should_be_rejected() {
+        let challenge = Challenge::MD5HashCash(MD5HashCashInput {
+            complexity: 9,
+            message: "hello".to_string(),
+        });
+        let answer = ChallengeAnswer::MonstrousMaze(MonstrousMazeOutput {
+            path: ">>".to_string(),
+        });
+        assert!(!verify_answer(&challenge, &answer));
+    }
+}
@@ -33,19 +33,7 @@ impl Challenge for MD5HashCash {
                     generated_seeds.push(seed);
                     // println!("seed: {}", seed);
 
-                    let seed_as_hexadecimal_string =
-                        complete_hexadecimal_seed_with_zero(format!("{:x}", seed));
-                    // println!("New seed as hexadecimal string: {}", seed_as_hexadecimal_string);
-
-                    let concatenated = format!(
-                        "{}{}",
-                        seed_as_hexadecimal_string,
-                        self.input.message.as_str()
-                    );
-
-                    let hashcode = md5::compute(concatenated);
-
-                    let hashcode_string = format!("{:x}", hashcode).to_uppercase();
+                    let hashcode_string = self.hashcode(seed);
                     // println!("Digest string: {}", hashcode_string);
 
                     let hashcode_binary =
@@ -68,12 +56,28 @@ impl Challenge for MD5HashCash {
     }
 
     fn verify(&self, answer: &Self::Output) -> bool {
+        // The hashcode must be the one of the seed, not just any hash with enough zeros
+        if !self
+            .hashcode(answer.seed)
+            .eq_ignore_ascii_case(&answer.hashcode)
+        {
+            return false;
+        }
         let hash_cash_client_answer_in_binary =
-            challenges::convert_string_to_binary(answer.hashcode.clone());
+            challenges::convert_string_to_binary(answer.hashcode.to_uppercase());
         challenges::check_number_of_zero(hash_cash_client_answer_in_binary, self.input.complexity)
     }
 }
 
+impl MD5HashCash {
+    /// MD5 of the seed (16 uppercase hexadecimal digits) followed by the message
+    pub fn hashcode(&self, seed: u64) -> String {
+        let seed_as_hexadecimal_string = complete_hexadecimal_seed_with_zero(format!("{:x}", seed));
+        let concatenated = format!("{}{}", seed_as_hexadecimal_string, self.input.message);
+        format!("{:x}", md5::compute(concatenated)).to_uppercase()
+    }
+}
+
 pub fn generate_seed(already_generated_seed: &Vec<u64>) -> Option<u64> {
     if already_generated_seed.is_empty() {
         return Some(1);
@@ -118,6 +122,18 @@ mod hash_cash_tests {
         assert_eq!(verify_output, true);
     }
 
+    #[test]
+    fn hashcode_of_another_seed_should_not_be_verified() {
+        let hash_cash_challenge = MD5HashCash::new(MD5HashCashInput {
+            message: "hello".to_string(),
+            complexity: 9,
+        });
+        let mut output = hash_cash_challenge.solve();
+        output.seed += 1;
+
+        assert!(!hash_cash_challenge.verify(&output));
+    }
+
     #[test] // Takes time
     fn high_complexity_should_return_correct_seed_by_incrementation() {
         let hash_cash_input = MD5HashCashInput {
@@ -0,0 +1,185 @@
+use crate::challenges::monstrous_maze::{
+    trace_path, Grid, PathError, PathTrace, END_CHARACTER, MONSTER_CHARACTER,
+};
+use crate::MonstrousMazeOutput;
+use std::fmt::Write;
+
+/// Marks a monster fought on the path
+const MONSTER_FOUGHT: char = '*';
+/// Marks the cell where the path was rejected
+const FAILURE: char = '!';
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// The grid with the path drawn on it, then each move with the endurance left and the
+/// verdict of `verify`, in ANSI colours if `colors` is set.
+///
+/// Each cell reached shows the move that led to it, a monster fought shows `*` and the
+/// cell where the path was rejected shows `!`.
+pub fn render_path(grid: &Grid, output: &MonstrousMazeOutput, colors: bool) -> String {
+    let trace = trace_path(grid, &output.path);
+    let mut cells: Vec<Vec<char>> = grid
+        .grid
+        .iter()
+        .map(|line| line.chars().collect())
+        .collect();
+    for step in trace.steps.iter() {
+        let mark = if step.cell == MONSTER_CHARACTER {
+            MONSTER_FOUGHT
+        } else if step.cell == END_CHARACTER {
+            continue;
+        } else {
+            step.direction
+        };
+        set_cell(&mut cells, step.coordinates, mark);
+    }
+    if let Some(coordinates) = failure_coordinates(grid, &trace) {
+        set_cell(&mut cells, coordinates, FAILURE);
+    }
+
+    let mut rendered = String::new();
+    let _ = writeln!(
+        rendered,
+        "Endurance {}, path {:?}",
+        grid.endurance, output.path
+    );
+    for line in cells.iter() {
+        for cell in line.iter() {
+            match color_of(*cell, colors) {
+                Some(color) => {
+                    let _ = write!(rendered, "{}{}{}", color, cell, RESET);
+                }
+                None => rendered.push(*cell),
+            }
+        }
+        rendered.push('\n');
+    }
+    for (index, step) in trace.steps.iter().enumerate() {
+        let _ = writeln!(
+            rendered,
+            "{:>4} {} ({}, {}) {:<7} endurance {}",
+            index + 1,
+            step.direction,
+            step.coordinates.0,
+            step.coordinates.1,
+            match step.cell {
+                MONSTER_CHARACTER => "monster",
+                END_CHARACTER => "exit",
+                _ => "free",
+            },
+            step.endurance_left
+        );
+    }
+    let (color, verdict) = match (&trace.error, trace.steps.last()) {
+        (None, Some(last_step)) => (
+            GREEN,
+            format!(
+                "Valid: exit reached with endurance {}",
+                last_step.endurance_left
+            ),
+        ),
+        (None, None) => (GREEN, "Valid".to_string()),
+        (Some(error), _) => (RED, format!("Rejected: {}", error)),
+    };
+    if colors {
+        let _ = writeln!(rendered, "{}{}{}", color, verdict, RESET);
+    } else {
+        let _ = writeln!(rendered, "{}", verdict);
+    }
+    rendered
+}
+
+fn set_cell(cells: &mut [Vec<char>], (line, column): (i64, i64), mark: char) {
+    if let Some(cell) = cells
+        .get_mut(line as usize)
+        .and_then(|line| line.get_mut(column as usize))
+    {
+        *cell = mark;
+    }
+}
+
+/// The blocked cell, or the cell where the player ran out of endurance
+fn failure_coordinates(grid: &Grid, trace: &PathTrace) -> Option<(i64, i64)> {
+    match trace.error.as_ref()? {
+        PathError::Blocked(_, coordinates, _) => Some(*coordinates),
+        PathError::NoEnduranceLeft(_) => Some(match trace.steps.last() {
+            Some(step) => step.coordinates,
+            None => (grid.start.0 as i64, grid.start.1 as i64),
+        }),
+        _ => None,
+    }
+}
+
+fn color_of(cell: char, colors: bool) -> Option<&'static str> {
+    if !colors {
+        return None;
+    }
+    match cell {
+        '<' | '>' | '^' | 'v' => Some(GREEN),
+        MONSTER_FOUGHT | FAILURE => Some(RED),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod maze_render_tests {
+    use crate::challenges::maze_render::render_path;
+    use crate::challenges::monstrous_maze::Grid;
+    use crate::{MonstrousMazeInput, MonstrousMazeOutput};
+
+    fn grid() -> Grid {
+        Grid::new(MonstrousMazeInput {
+            endurance: 2,
+            grid: "|I M #|\n|    X|".to_string(),
+        })
+    }
+
+    fn render(path: &str) -> String {
+        render_path(
+            &grid(),
+            &MonstrousMazeOutput {
+                path: path.to_string(),
+            },
+            false,
+        )
+    }
+
+    #[test]
+    fn valid_path_should_be_drawn_with_the_endurance_at_each_step() {
+        assert_eq!(
+            render(">>>v>"),
+            "Endurance 2, path \">>>v>\"\n\
+             |I>*>#|\n\
+             |   vX|\n\
+             \x20  1 > (0, 2) free    endurance 2\n\
+             \x20  2 > (0, 3) monster endurance 1\n\
+             \x20  3 > (0, 4) free    endurance 1\n\
+             \x20  4 v (1, 4) free    endurance 1\n\
+             \x20  5 > (1, 5) exit    endurance 1\n\
+             Valid: exit reached with endurance 1\n"
+        );
+    }
+
+    #[test]
+    fn rejected_path_should_show_where_it_failed() {
+        let blocked = render(">>>>");
+        assert!(blocked.contains("|I>*>!|\n"));
+        assert!(blocked.ends_with("Rejected: move 4 goes into a '#' cell\n"));
+
+        let too_short = render(">>");
+        assert!(too_short.ends_with("Rejected: the path stops before the exit\n"));
+        let invalid = render(">a");
+        assert!(invalid.ends_with("Rejected: move 2 'a' is not a direction\n"));
+    }
+
+    #[test]
+    fn colors_should_only_be_used_when_asked() {
+        let path = MonstrousMazeOutput {
+            path: ">>>v>".to_string(),
+        };
+        assert!(render_path(&grid(), &path, true).contains("\x1b[31m*\x1b[0m"));
+        assert!(!render_path(&grid(), &path, false).contains('\x1b'));
+    }
+}
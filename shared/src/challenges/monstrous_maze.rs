@@ -1,5 +1,9 @@
 use crate::challenges::Challenge;
 use crate::{MonstrousMazeInput, MonstrousMazeOutput};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
 #[derive(Debug, Clone)]
 pub struct MonstrousMaze {
@@ -19,6 +23,18 @@ impl Challenge for MonstrousMaze {
     }
 
     fn solve(&self) -> Self::Output {
+        self.solve_within(&MazeSearch::default())
+    }
+
+    fn verify(&self, answer: &Self::Output) -> bool {
+        let grid: Grid = Grid::new(self.input.clone());
+        trace_path(&grid, &answer.path).error.is_none()
+    }
+}
+
+impl MonstrousMaze {
+    /// Best path found within the limits of `search`, or an empty path when there is none
+    pub fn solve_within(&self, search: &MazeSearch) -> MonstrousMazeOutput {
         let mut final_output = MonstrousMazeOutput {
             path: "".to_string(),
         };
@@ -35,7 +51,7 @@ impl Challenge for MonstrousMaze {
             endurance_left: grid.endurance as i8,
         };
 
-        let possible_solutions = find_paths(&mut grid, grid_possible_solution);
+        let possible_solutions = find_paths(&mut grid, search, grid_possible_solution);
         match possible_solutions {
             Some(solutions) => {
                 if solutions.len() == 0 {
@@ -84,49 +100,6 @@ impl Challenge for MonstrousMaze {
             }
         }
     }
-
-    fn verify(&self, answer: &Self::Output) -> bool {
-        if answer.path.is_empty() {
-            return false;
-        }
-        let mut endurance_left = self.input.endurance as i8;
-        let grid: Grid = Grid::new(self.input.clone());
-        let mut current_coordinates = (grid.start.0 as i64, grid.start.1 as i64);
-
-        for character in answer.path.chars() {
-            if endurance_left <= 0 {
-                return false;
-            }
-            let next_coordinates = match character {
-                '<' => (current_coordinates.0, current_coordinates.1 - 1),
-                '>' => (current_coordinates.0, current_coordinates.1 + 1),
-                '^' => (current_coordinates.0 - 1, current_coordinates.1),
-                'v' => (current_coordinates.0 + 1, current_coordinates.1),
-                _ => panic!("Invalid character in path"),
-            };
-            if is_coordinates_in_grid(next_coordinates, &grid) {
-                current_coordinates = next_coordinates;
-                let current_line: String = grid.grid[current_coordinates.0 as usize].clone();
-                let current_char: char = current_line
-                    .chars()
-                    .nth(current_coordinates.1 as usize)
-                    .unwrap() as char;
-                if current_char == MONSTER_CHARACTER {
-                    endurance_left -= 1;
-                } else if current_char == END_CHARACTER
-                    && current_coordinates == (grid.end.0 as i64, grid.end.1 as i64)
-                {
-                    return endurance_left > 0;
-                } else if current_char != FREE_WAY_CHARACTER {
-                    return false;
-                }
-            } else {
-                return false;
-            }
-        }
-
-        true
-    }
 }
 
 pub struct Grid {
@@ -136,6 +109,26 @@ pub struct Grid {
     pub endurance: u8,
 }
 
+/// Limits of a maze search and what it has explored so far
+pub struct MazeSearch {
+    /// Longer paths are not explored
+    pub max_path_length: usize,
+    /// Paths are no longer explored after it
+    pub deadline: Option<Instant>,
+    /// Number of states explored so far, readable while the search runs
+    pub explored: Arc<AtomicU64>,
+}
+
+impl Default for MazeSearch {
+    fn default() -> Self {
+        MazeSearch {
+            max_path_length: usize::MAX,
+            deadline: None,
+            explored: Arc::default(),
+        }
+    }
+}
+
 impl Grid {
     pub fn new(input: MonstrousMazeInput) -> Grid {
         let split_grid = input
@@ -177,6 +170,133 @@ impl Grid {
     }
 }
 
+/// One move of a path, as checked by `verify`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathStep {
+    pub direction: char,
+    /// Line and column of the cell reached
+    pub coordinates: (i64, i64),
+    pub cell: char,
+    /// Endurance once the monster of the cell, if any, was fought
+    pub endurance_left: i8,
+}
+
+/// Why a path is not a valid answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    Empty,
+    /// The move at this index is not one of `<`, `>`, `^` and `v`
+    InvalidDirection(usize, char),
+    /// The move at this index leaves the grid
+    OutOfGrid(usize),
+    /// The move at this index goes into the cell at these coordinates, which is neither
+    /// free nor the exit
+    Blocked(usize, (i64, i64), char),
+    /// The player died from the monsters before the move at this index, or on the exit
+    NoEnduranceLeft(usize),
+    /// The path ended before the exit
+    StoppedBeforeExit,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "the path is empty"),
+            PathError::InvalidDirection(index, direction) => {
+                write!(f, "move {} {:?} is not a direction", index + 1, direction)
+            }
+            PathError::OutOfGrid(index) => write!(f, "move {} leaves the grid", index + 1),
+            PathError::Blocked(index, _, cell) => {
+                write!(f, "move {} goes into a {:?} cell", index + 1, cell)
+            }
+            PathError::NoEnduranceLeft(index) => {
+                write!(f, "no endurance left at move {}", index + 1)
+            }
+            PathError::StoppedBeforeExit => write!(f, "the path stops before the exit"),
+        }
+    }
+}
+
+/// Moves of a path until the exit or the first error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTrace {
+    pub steps: Vec<PathStep>,
+    /// `None` when the path is a valid answer
+    pub error: Option<PathError>,
+}
+
+/// Follows `path` from the start of `grid` the way `verify` checks it. Moves after the
+/// exit are ignored.
+pub fn trace_path(grid: &Grid, path: &str) -> PathTrace {
+    let mut trace = PathTrace {
+        steps: vec![],
+        error: None,
+    };
+    if path.is_empty() {
+        trace.error = Some(PathError::Empty);
+        return trace;
+    }
+    let mut endurance_left = grid.endurance as i8;
+    let mut current_coordinates = (grid.start.0 as i64, grid.start.1 as i64);
+
+    for (index, direction) in path.chars().enumerate() {
+        if endurance_left <= 0 {
+            trace.error = Some(PathError::NoEnduranceLeft(index));
+            return trace;
+        }
+        let next_coordinates = match direction {
+            '<' => (current_coordinates.0, current_coordinates.1 - 1),
+            '>' => (current_coordinates.0, current_coordinates.1 + 1),
+            '^' => (current_coordinates.0 - 1, current_coordinates.1),
+            'v' => (current_coordinates.0 + 1, current_coordinates.1),
+            _ => {
+                trace.error = Some(PathError::InvalidDirection(index, direction));
+                return trace;
+            }
+        };
+        if !is_coordinates_in_grid(next_coordinates, grid) {
+            trace.error = Some(PathError::OutOfGrid(index));
+            return trace;
+        }
+        current_coordinates = next_coordinates;
+        let cell = match grid.grid[current_coordinates.0 as usize]
+            .chars()
+            .nth(current_coordinates.1 as usize)
+        {
+            Some(cell) => cell,
+            // A line shorter than the first one
+            None => {
+                trace.error = Some(PathError::OutOfGrid(index));
+                return trace;
+            }
+        };
+        if cell == MONSTER_CHARACTER {
+            endurance_left -= 1;
+        } else if cell != FREE_WAY_CHARACTER
+            && !(cell == END_CHARACTER
+                && current_coordinates == (grid.end.0 as i64, grid.end.1 as i64))
+        {
+            trace.error = Some(PathError::Blocked(index, current_coordinates, cell));
+            return trace;
+        }
+        trace.steps.push(PathStep {
+            direction,
+            coordinates: current_coordinates,
+            cell,
+            endurance_left,
+        });
+        if cell == END_CHARACTER {
+            if endurance_left <= 0 {
+                trace.error = Some(PathError::NoEnduranceLeft(index));
+            }
+            return trace;
+        }
+    }
+
+    trace.error = Some(PathError::StoppedBeforeExit);
+    trace
+}
+
 pub struct GridPossibleSolution {
     pub current_coordinates: (i64, i64),
     pub path_taken: String,
@@ -185,10 +305,10 @@ pub struct GridPossibleSolution {
     pub endurance_left: i8,
 }
 
-const START_CHARACTER: char = 'I';
-const END_CHARACTER: char = 'X';
-const MONSTER_CHARACTER: char = 'M';
-const FREE_WAY_CHARACTER: char = ' ';
+pub const START_CHARACTER: char = 'I';
+pub const END_CHARACTER: char = 'X';
+pub const MONSTER_CHARACTER: char = 'M';
+pub const FREE_WAY_CHARACTER: char = ' ';
 
 /// Get best path by used endurance and path length
 pub fn get_best_path(
@@ -205,6 +325,7 @@ pub fn get_best_path(
 
 pub fn find_paths(
     grid: &mut Grid,
+    search: &MazeSearch,
     mut grid_possible_solution: GridPossibleSolution,
 ) -> Option<Vec<GridPossibleSolution>> {
     if grid_possible_solution
@@ -216,6 +337,14 @@ pub fn find_paths(
     if grid_possible_solution.endurance_left <= 0 {
         return Some(vec![grid_possible_solution]);
     }
+    if grid_possible_solution.path_taken.len() > search.max_path_length
+        || search
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    {
+        return Some(vec![]);
+    }
+    search.explored.fetch_add(1, Ordering::Relaxed);
 
     grid_possible_solution
         .visited_coordinates
@@ -248,10 +377,10 @@ pub fn find_paths(
 
         let mut all_paths: Vec<GridPossibleSolution> = vec![];
 
-        go_to_right(&mut all_paths, &grid_possible_solution, grid);
-        go_to_top(&mut all_paths, &grid_possible_solution, grid);
-        go_to_left(&mut all_paths, &grid_possible_solution, grid);
-        go_to_bottom(&mut all_paths, &grid_possible_solution, grid);
+        go_to_right(&mut all_paths, &grid_possible_solution, grid, search);
+        go_to_top(&mut all_paths, &grid_possible_solution, grid, search);
+        go_to_left(&mut all_paths, &grid_possible_solution, grid, search);
+        go_to_bottom(&mut all_paths, &grid_possible_solution, grid, search);
 
         Some(all_paths)
     } else {
@@ -263,6 +392,7 @@ fn go_to_left(
     all_paths: &mut Vec<GridPossibleSolution>,
     grid_possible_solution: &GridPossibleSolution,
     grid: &mut Grid,
+    search: &MazeSearch,
 ) {
     let left_direction = '<';
     let left_coordinates = (
@@ -274,6 +404,7 @@ fn go_to_left(
         left_coordinates,
         grid_possible_solution,
         grid,
+        search,
         all_paths,
     );
 }
@@ -282,6 +413,7 @@ fn go_to_top(
     all_paths: &mut Vec<GridPossibleSolution>,
     grid_possible_solution: &GridPossibleSolution,
     grid: &mut Grid,
+    search: &MazeSearch,
 ) {
     let top_direction = '^';
     let top_coordinates = (
@@ -293,6 +425,7 @@ fn go_to_top(
         top_coordinates,
         grid_possible_solution,
         grid,
+        search,
         all_paths,
     );
 }
@@ -301,6 +434,7 @@ fn go_to_right(
     all_paths: &mut Vec<GridPossibleSolution>,
     grid_possible_solution: &GridPossibleSolution,
     grid: &mut Grid,
+    search: &MazeSearch,
 ) {
     let right_direction = '>';
     let right_coordinates = (
@@ -312,6 +446,7 @@ fn go_to_right(
         right_coordinates,
         grid_possible_solution,
         grid,
+        search,
         all_paths,
     );
 }
@@ -320,6 +455,7 @@ fn go_to_bottom(
     all_paths: &mut Vec<GridPossibleSolution>,
     grid_possible_solution: &GridPossibleSolution,
     grid: &mut Grid,
+    search: &MazeSearch,
 ) {
     let bottom_direction = 'v';
     let bottom_coordinates = (
@@ -331,6 +467,7 @@ fn go_to_bottom(
         bottom_coordinates,
         grid_possible_solution,
         grid,
+        search,
         all_paths,
     );
 }
@@ -340,6 +477,7 @@ fn move_in_maze(
     new_coordinates: (i64, i64),
     grid_possible_solution: &GridPossibleSolution,
     grid: &mut Grid,
+    search: &MazeSearch,
     all_paths: &mut Vec<GridPossibleSolution>,
 ) {
     if is_coordinates_in_grid(new_coordinates, grid) {
@@ -350,7 +488,7 @@ fn move_in_maze(
             success: false,
             endurance_left: grid_possible_solution.endurance_left,
         };
-        match find_paths(grid, new_grid_possible_solution) {
+        match find_paths(grid, search, new_grid_possible_solution) {
             Some(mut paths) => {
                 all_paths.append(&mut paths);
             }
@@ -373,9 +511,12 @@ fn is_coordinates_in_grid(coordinates: (i64, i64), grid: &Grid) -> bool {
 
 #[cfg(test)]
 mod monstrous_maze_tests {
-    use crate::challenges::monstrous_maze::MonstrousMaze;
+    use crate::challenges::monstrous_maze::{MazeSearch, MonstrousMaze};
     use crate::challenges::Challenge;
     use crate::{MonstrousMazeInput, MonstrousMazeOutput};
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::time::Instant;
 
     #[test]
     fn simple_maze_without_monster_and_low_complexity_should_find_path() {
@@ -564,4 +705,54 @@ mod monstrous_maze_tests {
         let verify_output = monstrous_maze_challenge.verify(&output);
         assert_eq!(verify_output, false);
     }
+
+    #[test]
+    fn path_with_invalid_characters_should_return_false_when_verify() {
+        let monstrous_maze_input: MonstrousMazeInput = MonstrousMazeInput {
+            endurance: 1,
+            grid: "|I   X|".to_string(),
+        };
+        let monstrous_maze_challenge = MonstrousMaze::new(monstrous_maze_input);
+        let output: MonstrousMazeOutput = MonstrousMazeOutput {
+            path: ">>?>".to_string(),
+        };
+        let verify_output = monstrous_maze_challenge.verify(&output);
+        assert!(!verify_output);
+    }
+
+    #[test]
+    fn path_stopping_before_the_exit_should_return_false_when_verify() {
+        let monstrous_maze_input: MonstrousMazeInput = MonstrousMazeInput {
+            endurance: 1,
+            grid: "|I   X|".to_string(),
+        };
+        let monstrous_maze_challenge = MonstrousMaze::new(monstrous_maze_input);
+        let output: MonstrousMazeOutput = MonstrousMazeOutput {
+            path: ">>".to_string(),
+        };
+        let verify_output = monstrous_maze_challenge.verify(&output);
+        assert!(!verify_output);
+    }
+
+    #[test]
+    fn paths_longer_than_the_limit_should_not_be_explored() {
+        let monstrous_maze_input: MonstrousMazeInput = MonstrousMazeInput {
+            endurance: 1,
+            grid: "|I   X|".to_string(),
+        };
+        let monstrous_maze_challenge = MonstrousMaze::new(monstrous_maze_input);
+        let explored = Arc::default();
+        let path = |max_path_length, deadline| {
+            let search = MazeSearch {
+                max_path_length,
+                deadline,
+                explored: Arc::clone(&explored),
+            };
+            monstrous_maze_challenge.solve_within(&search).path
+        };
+        assert_eq!(path(3, None), "");
+        assert_eq!(path(4, None), ">>>>");
+        assert_eq!(path(4, Some(Instant::now())), "");
+        assert!(explored.load(Ordering::Relaxed) > 0);
+    }
 }
@@ -1,9 +1,15 @@
 pub mod challenges;
+pub mod transcript;
 
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::io::{Read, Write};
 use std::net::TcpStream;
-use std::str::from_utf8;
+use transcript::{Direction, Transcript};
+
+/// Largest JSON message [`try_read_message`] accepts; the size prefix of a bigger frame
+/// is rejected before anything is allocated
+pub const MAX_MESSAGE_SIZE: usize = 1 << 20;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Welcome {
@@ -15,6 +21,18 @@ pub struct Subscribe {
     pub name: String,
 }
 
+/// Opens a controller connection with the token given to the server as `--admin-token`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdminLogin {
+    pub token: String,
+}
+
+/// Moves a connection to a named game room, before `Subscribe` or admin commands
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JoinRoom {
+    pub room: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum SubscribeResult {
     Ok,
@@ -30,6 +48,30 @@ pub enum SubscribeError {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PublicLeaderBoard(pub Vec<PublicPlayer>);
 
+/// Renders the leaderboard as a text table, one player per line
+impl fmt::Display for PublicLeaderBoard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:<22} {:>6} {:>6} {:>7} {:>10}",
+            "name", "stream_id", "score", "steps", "active", "used_time"
+        )?;
+        for player in &self.0 {
+            writeln!(
+                f,
+                "{:<24} {:<22} {:>6} {:>6} {:>7} {:>10.3}",
+                player.name,
+                player.stream_id,
+                player.score,
+                player.steps,
+                player.is_active,
+                player.total_used_time
+            )?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PublicPlayer {
     pub name: String,
@@ -87,6 +129,30 @@ pub struct RoundSummary {
     pub chain: Vec<ReportedChallengeResult>,
 }
 
+/// `MD5HashCash: dark_salad Ok 0.120s -> free_patato, free_patato Timeout`
+impl fmt::Display for RoundSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.challenge)?;
+        for (index, reported) in self.chain.iter().enumerate() {
+            let separator = if index == 0 { " " } else { ", " };
+            write!(f, "{}{} ", separator, reported.name)?;
+            match &reported.value {
+                ChallengeValue::Ok {
+                    used_time,
+                    next_target,
+                } => write!(f, "Ok {:.3}s -> {}", used_time, next_target)?,
+                ChallengeValue::BadResult {
+                    used_time,
+                    next_target,
+                } => write!(f, "BadResult {:.3}s -> {}", used_time, next_target)?,
+                ChallengeValue::Timeout => write!(f, "Timeout")?,
+                ChallengeValue::Unreachable => write!(f, "Unreachable")?,
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ReportedChallengeResult {
     pub name: String,
@@ -97,20 +163,8 @@ pub struct ReportedChallengeResult {
 pub enum ChallengeValue {
     Unreachable,
     Timeout,
-    BadResult(BadResult),
-    Ok(Ok),
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct BadResult {
-    pub used_time: f64,
-    pub next_target: String,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Ok {
-    pub used_time: f64,
-    pub next_target: String,
+    BadResult { used_time: f64, next_target: String },
+    Ok { used_time: f64, next_target: String },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -130,6 +184,18 @@ pub enum Message {
     ChallengeResult(ChallengeResult),
     RoundSummary(RoundSummary),
     EndOfGame(EndOfGame),
+    /// First message of a controller: the server answers `SubscribeResult::Ok` if the
+    /// token is its admin token and closes the connection otherwise
+    AdminLogin(AdminLogin),
+    /// Admin only: asks the server for the current `PublicLeaderBoard`
+    ListPlayers,
+    /// Admin only: ends the game right away with an `EndOfGame`
+    AbortGame,
+    /// Moves the connection to a room other than the server default one
+    JoinRoom(JoinRoom),
+    /// Sent instead of `Subscribe` to follow the game without playing: the server answers
+    /// `SubscribeResult::Ok`, the current `PublicLeaderBoard`, then every broadcast
+    Spectate,
 }
 
 pub fn send_message(stream: &TcpStream, message: Message) {
@@ -159,35 +225,228 @@ fn serialize_message(message: Message) -> serde_json::Result<String> {
     Ok(serialized)
 }
 
-pub fn read_message(mut stream: &TcpStream) -> String {
-    let mut data = [0_u8; 4];
-    match stream.read_exact(&mut data) {
-        Ok(_) => read_message_data(stream, data),
-        Err(e) => {
-            panic!("Failed to read message size: {}", e);
-        }
+/// Reads the next framed message without panicking on I/O or UTF-8 errors.
+///
+/// Frames over [`MAX_MESSAGE_SIZE`] are reported as [`std::io::ErrorKind::InvalidData`].
+pub fn try_read_message(mut stream: &TcpStream) -> std::io::Result<String> {
+    let mut size = [0_u8; 4];
+    stream.read_exact(&mut size)?;
+    let size = u32::from_be_bytes(size) as usize;
+    if size > MAX_MESSAGE_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "message of {} bytes exceeds {} bytes",
+                size, MAX_MESSAGE_SIZE
+            ),
+        ));
     }
+    let mut data: Vec<u8> = vec![0u8; size];
+    stream.read_exact(&mut data)?;
+    String::from_utf8(data).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
 }
 
-fn read_message_data(mut stream: &TcpStream, data: [u8; 4]) -> String {
-    let size = u32::from_be_bytes(data) as usize;
-    let mut data: Vec<u8> = vec![0u8; size];
-    match stream.read_exact(&mut data) {
-        Ok(_) => vec_to_string(data),
-        Err(e) => {
-            panic!("Failed to read message data: {}", e);
-        }
+/// Reads and deserializes the next framed message.
+///
+/// Malformed JSON is reported as an [`std::io::ErrorKind::InvalidData`] error.
+pub fn receive_message(stream: &TcpStream) -> std::io::Result<Message> {
+    let response = try_read_message(stream)?;
+    serde_json::from_str(&response)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Same as [`write_message`], also recording the message in `transcript` if any.
+pub fn write_recorded_message(
+    stream: &TcpStream,
+    message: Message,
+    transcript: Option<&Transcript>,
+    round: u32,
+) -> std::io::Result<()> {
+    if let Some(transcript) = transcript {
+        record_message(transcript, Direction::Sent, stream, round, &message);
     }
+    write_message(stream, message)
 }
 
-fn vec_to_string(data: Vec<u8>) -> String {
-    match from_utf8(&data) {
-        Ok(response) => {
-            println!("{}", response);
-            response.to_string()
+/// Records `message` with the peer address of `stream`; a failing transcript never
+/// interrupts the game.
+pub fn record_message(
+    transcript: &Transcript,
+    direction: Direction,
+    stream: &TcpStream,
+    round: u32,
+    message: &Message,
+) {
+    let stream_id = stream
+        .peer_addr()
+        .map_or_else(|_| "unknown".to_string(), |addr| addr.to_string());
+    if let Err(err) = transcript.record(direction, &stream_id, round, message) {
+        println!("Cannot write the transcript: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod protocol_tests {
+    use crate::{
+        Challenge, ChallengeAnswer, ChallengeResult, ChallengeValue, EndOfGame, MD5HashCashInput,
+        MD5HashCashOutput, Message, PublicLeaderBoard, PublicPlayer, ReportedChallengeResult,
+        RoundSummary, Subscribe, SubscribeError, SubscribeResult, Welcome,
+    };
+
+    /// Examples of the README protocol table, one message per golden file
+    const README_EXAMPLES: [(&str, &str); 9] = [
+        ("hello", include_str!("../golden/hello.json")),
+        ("welcome", include_str!("../golden/welcome.json")),
+        ("subscribe", include_str!("../golden/subscribe.json")),
+        (
+            "subscribe_result",
+            include_str!("../golden/subscribe_result.json"),
+        ),
+        (
+            "public_leader_board",
+            include_str!("../golden/public_leader_board.json"),
+        ),
+        ("challenge", include_str!("../golden/challenge.json")),
+        (
+            "challenge_result",
+            include_str!("../golden/challenge_result.json"),
+        ),
+        (
+            "round_summary",
+            include_str!("../golden/round_summary.json"),
+        ),
+        ("end_of_game", include_str!("../golden/end_of_game.json")),
+    ];
+
+    /// `BadResult` and `Timeout` as described by the README types table
+    const ROUND_SUMMARY_FAILURES: &str = include_str!("../golden/round_summary_failures.json");
+
+    fn serialize(message: Message) -> String {
+        serde_json::to_string(&message).unwrap()
+    }
+
+    fn leader_board() -> PublicLeaderBoard {
+        PublicLeaderBoard(vec![
+            PublicPlayer {
+                name: "free_patato".to_string(),
+                stream_id: "127.0.0.1".to_string(),
+                score: 10,
+                steps: 20,
+                is_active: true,
+                total_used_time: 1.234,
+            },
+            PublicPlayer {
+                name: "dark_salad".to_string(),
+                stream_id: "127.0.0.1".to_string(),
+                score: 6,
+                steps: 200,
+                is_active: true,
+                total_used_time: 0.1234,
+            },
+        ])
+    }
+
+    #[test]
+    fn readme_examples_should_round_trip_byte_for_byte() {
+        for (name, golden) in README_EXAMPLES
+            .iter()
+            .chain([&("round_summary_failures", ROUND_SUMMARY_FAILURES)])
+        {
+            let golden = golden.trim_end();
+            let message: Message = serde_json::from_str(golden)
+                .unwrap_or_else(|err| panic!("{} does not deserialize: {}", name, err));
+            assert_eq!(serialize(message), golden, "{}", name);
         }
-        Err(e) => {
-            panic!("Failed to convert message data to string: {}", e);
+    }
+
+    #[test]
+    fn messages_should_serialize_like_readme_examples() {
+        let messages = [
+            Message::Hello,
+            Message::Welcome(Welcome { version: 1 }),
+            Message::Subscribe(Subscribe {
+                name: "free_patato".to_string(),
+            }),
+            Message::SubscribeResult(SubscribeResult::Err(SubscribeError::InvalidName)),
+            Message::PublicLeaderBoard(leader_board()),
+            Message::Challenge(Challenge::MD5HashCash(MD5HashCashInput {
+                complexity: 5,
+                message: "Hello".to_string(),
+            })),
+            Message::ChallengeResult(ChallengeResult {
+                answer: ChallengeAnswer::MD5HashCash(MD5HashCashOutput {
+                    seed: 12345678,
+                    hashcode: "68B329DA9893E34099C7D8AD5CB9C940".to_string(),
+                }),
+                next_target: "dark_salad".to_string(),
+            }),
+            Message::RoundSummary(RoundSummary {
+                challenge: "MD5HashCash".to_string(),
+                chain: vec![
+                    ReportedChallengeResult {
+                        name: "free_patato".to_string(),
+                        value: ChallengeValue::Ok {
+                            used_time: 0.1,
+                            next_target: "dark_salad".to_string(),
+                        },
+                    },
+                    ReportedChallengeResult {
+                        name: "dark_salad".to_string(),
+                        value: ChallengeValue::Unreachable,
+                    },
+                ],
+            }),
+            Message::EndOfGame(EndOfGame {
+                leader_board: leader_board(),
+            }),
+        ];
+        for (message, (name, golden)) in messages.into_iter().zip(README_EXAMPLES) {
+            assert_eq!(serialize(message), golden.trim_end(), "{}", name);
         }
     }
+
+    #[test]
+    fn failed_challenge_values_should_serialize_like_readme_types() {
+        let message = Message::RoundSummary(RoundSummary {
+            challenge: "MonstrousMaze".to_string(),
+            chain: vec![
+                ReportedChallengeResult {
+                    name: "free_patato".to_string(),
+                    value: ChallengeValue::BadResult {
+                        used_time: 0.5,
+                        next_target: "dark_salad".to_string(),
+                    },
+                },
+                ReportedChallengeResult {
+                    name: "dark_salad".to_string(),
+                    value: ChallengeValue::Timeout,
+                },
+            ],
+        });
+        assert_eq!(serialize(message), ROUND_SUMMARY_FAILURES.trim_end());
+    }
+
+    #[test]
+    fn round_summary_should_display_the_whole_chain() {
+        let round_summary = RoundSummary {
+            challenge: "MD5HashCash".to_string(),
+            chain: vec![
+                ReportedChallengeResult {
+                    name: "dark_salad".to_string(),
+                    value: ChallengeValue::Ok {
+                        used_time: 0.12,
+                        next_target: "free_patato".to_string(),
+                    },
+                },
+                ReportedChallengeResult {
+                    name: "free_patato".to_string(),
+                    value: ChallengeValue::Timeout,
+                },
+            ],
+        };
+        assert_eq!(
+            round_summary.to_string(),
+            "MD5HashCash: dark_salad Ok 0.120s -> free_patato, free_patato Timeout"
+        );
+    }
 }
@@ -0,0 +1,123 @@
+use crate::Message;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// One line of a transcript: a message as seen by whoever recorded it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptEntry {
+    /// Seconds since the Unix epoch
+    pub timestamp: f64,
+    pub direction: Direction,
+    /// Address of the peer the message was exchanged with
+    pub stream_id: String,
+    /// `0` during registrations, then the number of the round in progress
+    pub round: u32,
+    pub message: Message,
+}
+
+/// A JSON Lines file that receives one [`TranscriptEntry`] per recorded message.
+///
+/// Lines are written whole and right away, so a transcript stays readable up to the
+/// last message even if the process dies.
+#[derive(Debug)]
+pub struct Transcript {
+    file: Mutex<File>,
+}
+
+impl Transcript {
+    /// Creates (or truncates) the transcript file at `path`.
+    pub fn create<P: AsRef<Path>>(path: P) -> std::io::Result<Transcript> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(Transcript {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn record(
+        &self,
+        direction: Direction,
+        stream_id: &str,
+        round: u32,
+        message: &Message,
+    ) -> std::io::Result<()> {
+        let entry = TranscriptEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |elapsed| elapsed.as_secs_f64()),
+            direction,
+            stream_id: stream_id.to_string(),
+            round,
+            message: message.clone(),
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(line.as_bytes())
+    }
+}
+
+/// Reads every entry of the transcript at `path`, skipping empty lines.
+pub fn read_transcript<P: AsRef<Path>>(path: P) -> std::io::Result<Vec<TranscriptEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(serde_json::from_str(&line)?);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod transcript_tests {
+    use crate::transcript::{read_transcript, Direction, Transcript};
+    use crate::{Message, Subscribe};
+
+    #[test]
+    fn recorded_messages_should_be_read_back_in_order() {
+        let path =
+            std::env::temp_dir().join(format!("patate-transcript-{}.jsonl", std::process::id()));
+        let transcript = Transcript::create(&path).unwrap();
+        transcript
+            .record(Direction::Received, "127.0.0.1:4242", 0, &Message::Hello)
+            .unwrap();
+        transcript
+            .record(
+                Direction::Sent,
+                "127.0.0.1:4242",
+                1,
+                &Message::Subscribe(Subscribe {
+                    name: "free_potato".to_string(),
+                }),
+            )
+            .unwrap();
+
+        let entries = read_transcript(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].direction, Direction::Received);
+        assert!(matches!(entries[0].message, Message::Hello));
+        assert_eq!(entries[1].stream_id, "127.0.0.1:4242");
+        assert_eq!(entries[1].round, 1);
+        assert!(matches!(entries[1].message, Message::Subscribe(..)));
+    }
+}
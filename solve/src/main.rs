@@ -0,0 +1,79 @@
+use clap::Parser;
+use shared::challenges::maze_render;
+use shared::challenges::monstrous_maze::Grid;
+use shared::{challenges, Challenge, ChallengeAnswer};
+use std::io::Read;
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
+
+/// Exit status when `--verify` rejects the answer (`1` is left for errors)
+const REJECTED_EXIT_CODE: i32 = 2;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Challenge JSON as sent by the server, e.g.
+    /// `{"MD5HashCash":{"complexity":5,"message":"Hello"}}`; read on the standard input
+    /// when omitted
+    #[clap(value_parser)]
+    challenge: Option<PathBuf>,
+    /// Check the answer with the challenge and exit with status 2 if it is rejected
+    #[clap(long, value_parser)]
+    verify: bool,
+    /// Print the time spent solving on the standard error
+    #[clap(long, value_parser)]
+    time: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+    let json = match &args.challenge {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|err| format!("Cannot read {}: {}", path.display(), err)),
+        None => {
+            let mut json = String::new();
+            std::io::stdin()
+                .read_to_string(&mut json)
+                .map(|_| json)
+                .map_err(|err| format!("Cannot read the standard input: {}", err))
+        }
+    };
+    let challenge: Challenge = match json.and_then(|json| {
+        serde_json::from_str(&json).map_err(|err| format!("Invalid challenge: {}", err))
+    }) {
+        Ok(challenge) => challenge,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    let started = Instant::now();
+    let answer = challenges::solve_challenge(&challenge);
+    let elapsed = started.elapsed();
+    match serde_json::to_string(&answer) {
+        Ok(json) => println!("{}", json),
+        Err(err) => {
+            eprintln!("Cannot serialize the answer: {}", err);
+            process::exit(1);
+        }
+    }
+    if args.time {
+        eprintln!("Solved in {:.3}s", elapsed.as_secs_f64());
+    }
+    if args.verify {
+        if challenges::verify_answer(&challenge, &answer) {
+            eprintln!("Valid answer");
+        } else {
+            eprintln!("Rejected answer");
+            if let (Challenge::MonstrousMaze(input), ChallengeAnswer::MonstrousMaze(output)) =
+                (&challenge, &answer)
+            {
+                let grid = Grid::new(input.clone());
+                eprint!("{}", maze_render::render_path(&grid, output, false));
+            }
+            process::exit(REJECTED_EXIT_CODE);
+        }
+    }
+}